@@ -71,28 +71,24 @@
 // Find the input noun and verb that cause the program to produce the output 19690720. What is 100 * noun + verb? (For example, if noun=12 and verb=2, the answer would be 1202.)
 //
 
-use std::fs;
-
-fn recalculate(position: usize, numbers: &mut Vec<i32>) {
+fn recalculate(position: usize, numbers: &mut Vec<i64>) {
     match numbers.get(position) {
         Some(1) |  Some(2) => {
             let pos_a = *numbers.get(position + 1).unwrap() as usize;
             let pos_b = *numbers.get(position + 2).unwrap() as usize;
             let pos_c = *numbers.get(position + 3).unwrap() as usize;
-            let a = numbers.get(pos_a).unwrap().clone();
-            let b = numbers.get(pos_b).unwrap().clone();
+            let a = *numbers.get(pos_a).unwrap();
+            let b = *numbers.get(pos_b).unwrap();
 
             if numbers.get(position) == Some(&1) {
-                std::mem::replace(&mut numbers[pos_c], a + b);
+                numbers[pos_c] = a + b;
             } else {
-                std::mem::replace(&mut numbers[pos_c], a * b);
+                numbers[pos_c] = a * b;
             }
 
             recalculate(position + 4, numbers);
         },
-        Some(99) => {
-            return;
-        },
+        Some(99) => {},
         _ => {
             panic!("This should not happen");
         },
@@ -100,22 +96,20 @@ fn recalculate(position: usize, numbers: &mut Vec<i32>) {
 }
 
 fn main() {
-    let contents = fs::read_to_string("input.txt")
-        .expect("Something went wrong reading the file");
-
     let expected_result = 19690720;
 
-    let mut numbers: Vec<i32> = contents.split(',').map(|n| n.parse::<i32>().unwrap_or(0)).collect();
+    let mut numbers: Vec<i64> = intcode::load("input.txt")
+        .unwrap_or_else(|e| panic!("Something went wrong reading the file: {}", e));
     let original_numbers = numbers.clone();
 
     for noun in 0..=99 {
         for verb in 0..=99 {
-            std::mem::replace(&mut numbers[1], noun);
-            std::mem::replace(&mut numbers[2], verb);
+            numbers[1] = noun;
+            numbers[2] = verb;
 
             recalculate(0, &mut numbers);
 
-            let result = *numbers.get(0).unwrap();
+            let result = *numbers.first().unwrap();
 
             if result == expected_result {
                 println!("{}", noun * 100 + verb);
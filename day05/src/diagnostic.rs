@@ -0,0 +1,236 @@
+// Making sense of what the TEST diagnostic program outputs. Every output but the last is the result of a self
+// test, 0 if it passed and how far off the result was if it didn't. The last one, followed immediately by a halt,
+// is the diagnostic code.
+//
+// For a failed test the instructions executed since the previous output are searched for the one that computed
+// the failing value: starting from the cell that was output, each instruction writing a cell the value came from
+// is followed back to the cells it read. The earliest instruction found that way is the one most likely at
+// fault, the ones after it usually just subtract the expected result.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use intcode::{Error, Input, Machine, State};
+
+/// An instruction as it was executed, together with its parameters.
+#[derive(Debug, Clone)]
+pub struct Executed {
+    pub address: usize,
+    pub words: Vec<i64>,
+}
+
+impl Executed {
+    fn opcode(&self) -> i64 {
+        self.words[0] % 100
+    }
+
+    fn modes(&self) -> Vec<i64> {
+        (1..self.words.len()).map(|n| self.words[0] / 10_i64.pow(n as u32 + 1) % 10).collect()
+    }
+
+    // The cells read in position mode.
+    fn reads(&self) -> Vec<i64> {
+        let parameters = match self.opcode() {
+            1 | 2 | 7 | 8 => 2,
+            4..=6 => self.words.len() - 1,
+            _ => 0,
+        };
+
+        self.modes().iter().zip(&self.words[1..]).take(parameters)
+            .filter(|(mode, _)| **mode == 0).map(|(_, cell)| *cell).collect()
+    }
+
+    fn writes(&self) -> Option<i64> {
+        match self.opcode() {
+            1 | 2 | 7 | 8 => Some(self.words[3]),
+            3 => Some(self.words[1]),
+            _ => None,
+        }
+    }
+}
+
+/// Everything a machine did: each instruction in the order they were executed, and the outputs, each with the
+/// number of instructions executed up to and including the output instruction.
+#[derive(Debug, Default)]
+pub struct Trace {
+    pub executed: Vec<Executed>,
+    pub outputs: Vec<(i64, usize)>,
+}
+
+impl Trace {
+    /// Runs the machine one instruction at a time until it halts or waits for input, recording what it does.
+    pub fn run<I: Input + ?Sized>(&mut self, machine: &mut Machine, input: &mut I) -> Result<State, Error> {
+        loop {
+            let address = machine.ip();
+            let opcode = machine.get(address) % 100;
+            let parameters = machine.instructions().get(opcode).map_or(0, |definition| definition.roles.len());
+            let words = (address..=address + parameters).map(|a| machine.get(a)).collect();
+            let mut outputs = Vec::new();
+
+            let state = machine.step(input, &mut outputs)?;
+
+            if state != State::WaitingForInput {
+                self.executed.push(Executed { address, words });
+            }
+
+            for value in outputs {
+                self.outputs.push((value, self.executed.len()));
+            }
+
+            if state != State::Running {
+                return Ok(state);
+            }
+        }
+    }
+}
+
+/// The instruction most likely responsible for a failed test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suspect {
+    pub address: usize,
+    pub opcode: i64,
+    /// The mode of every parameter, first parameter first.
+    pub modes: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test {
+    pub result: i64,
+    pub suspect: Option<Suspect>,
+}
+
+impl Test {
+    pub fn passed(&self) -> bool {
+        self.result == 0
+    }
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub tests: Vec<Test>,
+    /// `None` if the program didn't halt right after its last output.
+    pub code: Option<i64>,
+}
+
+impl Report {
+    pub fn new(trace: &Trace) -> Report {
+        let halted = trace.executed.last().is_some_and(|e| e.opcode() == 99);
+        let mut outputs = trace.outputs.as_slice();
+        let mut code = None;
+
+        if let Some(((value, count), rest)) = outputs.split_last() {
+            if halted && *count + 1 == trace.executed.len() {
+                code = Some(*value);
+                outputs = rest;
+            }
+        }
+
+        let mut start = 0;
+        let tests = outputs.iter().map(|(result, count)| {
+            let suspect = match result {
+                0 => None,
+                _ => Some(suspect(&trace.executed[start..*count])),
+            };
+
+            start = *count;
+            Test { result: *result, suspect }
+        }).collect();
+
+        Report { tests, code }
+    }
+
+    pub fn failed(&self) -> usize {
+        self.tests.iter().filter(|t| !t.passed()).count()
+    }
+}
+
+// The earliest instruction the output at the end of the executed instructions depends on.
+fn suspect(executed: &[Executed]) -> Suspect {
+    let (output, before) = executed.split_last().expect("A test ends with an output");
+    let mut cells: HashSet<i64> = output.reads().into_iter().collect();
+    let mut suspect = output;
+
+    for instruction in before.iter().rev() {
+        if cells.is_empty() {
+            break;
+        }
+
+        if let Some(cell) = instruction.writes() {
+            if cells.remove(&cell) {
+                cells.extend(instruction.reads());
+                suspect = instruction;
+            }
+        }
+    }
+
+    Suspect { address: suspect.address, opcode: suspect.opcode(), modes: suspect.modes() }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, test) in self.tests.iter().enumerate() {
+            match &test.suspect {
+                None => writeln!(f, "test {}: ok", i + 1)?,
+                Some(suspect) => {
+                    let modes: Vec<String> = suspect.modes.iter().map(|m| m.to_string()).collect();
+                    writeln!(f, "test {}: off by {}, most likely opcode {} with modes {} at {}",
+                        i + 1, test.result, suspect.opcode, modes.join(", "), suspect.address)?;
+                },
+            }
+        }
+
+        match self.code {
+            Some(code) => writeln!(f, "diagnostic code {}", code),
+            None => writeln!(f, "no diagnostic code, the program didn't halt after its last output"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    fn diagnose(program: &[i64], system: i64) -> Report {
+        let mut trace = Trace::default();
+        trace.run(&mut Machine::new(program), &mut VecDeque::from(vec![system])).unwrap();
+        Report::new(&trace)
+    }
+
+    #[test]
+    fn the_last_output_before_halting_is_the_code() {
+        let report = diagnose(&[104, 0, 104, 42, 99], 1);
+
+        assert_eq!(report.tests, vec![Test { result: 0, suspect: None }]);
+        assert_eq!(report.code, Some(42));
+
+        let report = diagnose(&[3, 0, 104, 0, 3, 0, 99], 1);
+
+        assert_eq!(report.tests.len(), 1);
+        assert_eq!(report.code, None);
+    }
+
+    #[test]
+    fn failed_tests_name_the_instruction_computing_the_result() {
+        // 2 + 2 is supposed to be 5, so the test is off by one and the addition gets the blame, not the
+        // subtraction of the expected result.
+        let program = [1, 15, 16, 17, 1001, 17, -5, 17, 4, 17, 104, 7, 99, 0, 0, 2, 2, 0];
+        let report = diagnose(&program, 1);
+        let suspect = Suspect { address: 0, opcode: 1, modes: vec![0, 0, 0] };
+
+        assert_eq!(report.tests, vec![Test { result: -1, suspect: Some(suspect) }]);
+        assert_eq!(report.code, Some(7));
+        assert_eq!(report.failed(), 1);
+    }
+
+    #[test]
+    fn the_air_conditioner_passes_every_test() {
+        let program = intcode::load("input.txt").unwrap();
+        let report = diagnose(&program, 1);
+
+        assert_eq!(report.tests.len(), 9);
+        assert_eq!(report.failed(), 0);
+        assert_eq!(report.code, Some(11193703));
+    }
+}
@@ -90,8 +90,15 @@
 //
 // What is the diagnostic code for system ID 5?
 
+mod diagnostic;
 
-use std::fs;
+use std::collections::VecDeque;
+use std::env;
+use std::process;
+
+use intcode::Machine;
+
+use diagnostic::{Report, Trace};
 
 fn pad(s: String) -> String {
     if s.len() == 1 {
@@ -103,7 +110,7 @@ fn pad(s: String) -> String {
     }
 }
 
-fn collect_parameters(s: &str, numbers: &Vec<i32>, position: usize) -> Vec<i32> {
+fn collect_parameters(s: &str, numbers: &[i64], position: usize) -> Vec<i64> {
     let mut parameters = Vec::new();
 
     s.chars().rev().enumerate().for_each(|(i, c)| {
@@ -117,12 +124,12 @@ fn collect_parameters(s: &str, numbers: &Vec<i32>, position: usize) -> Vec<i32>
     parameters
 }
 
-fn recalculate(position: usize, numbers: &mut Vec<i32>, inputs: &mut Vec<i32>) {
+fn recalculate(position: usize, numbers: &mut Vec<i64>, inputs: &mut Vec<i64>) {
     match numbers.get(position).map(|x| x.to_string()) {
         Some(x) if x.ends_with("3") => {
             let input = inputs.pop().expect("No more inputs available");
             let pos = numbers[position + 1] as usize;
-            std::mem::replace(&mut numbers[pos], input);
+            numbers[pos] = input;
             recalculate(position + 2, numbers, inputs);
         },
         Some(x) if x.ends_with("5") => {
@@ -157,9 +164,9 @@ fn recalculate(position: usize, numbers: &mut Vec<i32>, inputs: &mut Vec<i32>) {
             let pos = numbers[position + 3] as usize;
 
             if parameters[0] < parameters[1] {
-                std::mem::replace(&mut numbers[pos], 1);
+                numbers[pos] = 1;
             } else {
-                std::mem::replace(&mut numbers[pos], 0);
+                numbers[pos] = 0;
             }
 
             recalculate(position + 4, numbers, inputs);
@@ -172,20 +179,18 @@ fn recalculate(position: usize, numbers: &mut Vec<i32>, inputs: &mut Vec<i32>) {
             let pos = numbers[position + 3] as usize;
 
             if parameters[0] == parameters[1] {
-                std::mem::replace(&mut numbers[pos], 1);
+                numbers[pos] = 1;
             } else {
-                std::mem::replace(&mut numbers[pos], 0);
+                numbers[pos] = 0;
             }
 
             recalculate(position + 4, numbers, inputs);
         },
-        Some(x) if x.ends_with("99") => {
-            return;
-        },
+        Some(x) if x.ends_with("99") => {},
         Some(x) if x.ends_with("4") => {
             let s = x.to_string();
 
-            let val = if s.starts_with("0") || s == "4".to_string() {
+            let val = if s.starts_with("0") || s == "4" {
                 numbers[numbers[position + 1] as usize]
             } else {
                 numbers[position + 1]
@@ -205,9 +210,9 @@ fn recalculate(position: usize, numbers: &mut Vec<i32>, inputs: &mut Vec<i32>) {
             let b = parameters[1];
 
             if op == "01" {     // addition
-                std::mem::replace(&mut numbers[pos], a + b);
+                numbers[pos] = a + b;
             } else {            // multiplication
-                std::mem::replace(&mut numbers[pos], a * b);
+                numbers[pos] = a * b;
             }
 
             recalculate(position + 4, numbers, inputs);
@@ -218,12 +223,40 @@ fn recalculate(position: usize, numbers: &mut Vec<i32>, inputs: &mut Vec<i32>) {
     }
 }
 
+// Usage: day05 [--report] [SYSTEM ID]
+//
+// Runs the diagnostic program for the given system, 5 if there is none, and prints its outputs. With --report
+// the program runs on the shared Intcode machine instead, the outputs are split into the results of the self
+// tests and the diagnostic code, and every failed test names the instruction most likely at fault. The exit code
+// is 1 if any test failed.
 fn main() {
-    let contents = fs::read_to_string("input.txt")
-        .expect("Something went wrong reading the file");
+    let mut report = false;
+    let mut system = 5;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--report" => report = true,
+            id => system = id.parse().unwrap_or_else(|_| panic!("{} is not a system ID", id)),
+        }
+    }
+
+    let mut numbers: Vec<i64> = intcode::load("input.txt")
+        .unwrap_or_else(|e| panic!("Something went wrong reading the file: {}", e));
+
+    if !report {
+        let mut inputs: Vec<i64> = [system].to_vec();
+        recalculate(0, &mut numbers, &mut inputs);
+        return;
+    }
+
+    let mut trace = Trace::default();
+    trace.run(&mut Machine::new(&numbers), &mut VecDeque::from(vec![system]))
+        .unwrap_or_else(|e| panic!("The diagnostic program failed: {}", e));
 
-    let mut numbers: Vec<i32> = contents.split(',').map(|n| n.parse::<i32>().unwrap_or(0)).collect();
-    let mut inputs: Vec<i32> = [5].to_vec();
+    let report = Report::new(&trace);
+    print!("{}", report);
 
-    recalculate(0, &mut numbers, &mut inputs);
+    if report.failed() > 0 {
+        process::exit(1);
+    }
 }
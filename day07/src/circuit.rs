@@ -0,0 +1,465 @@
+// Amplifier circuits described in a small text format, one statement per line:
+//
+//     # the five amplifiers in a feedback loop, as in part two
+//     phases 5,6,7,8,9
+//     amplifier A ../input.txt
+//     amplifier B ../input.txt 7
+//     A -> B
+//     B -> A
+//     signal A 0
+//     output B
+//
+// `amplifier` takes a name, the path of its program relative to the circuit file and optionally a fixed phase
+// setting. Amplifiers without one get a distinct phase setting from `phases` each. `->` connects the output of
+// an amplifier to the input of another one, `signal` sends a value to an amplifier once all of them got their
+// phase settings, and `output` names the amplifier whose last signal goes to the thrusters.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use intcode::taint::Taint;
+use intcode::{Input, Machine, Network, Outcome, Output, Route, State};
+
+use crate::provenance::{Origin, Provenance};
+use crate::search::Assignments;
+use crate::timeline::Timeline;
+
+#[derive(Debug)]
+pub struct Amplifier {
+    pub name: String,
+    pub program: Vec<i64>,
+    pub phase: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct Circuit {
+    pub amplifiers: Vec<Amplifier>,
+    pub edges: Vec<(usize, usize)>,
+    pub signals: Vec<(usize, i64)>,
+    pub output: usize,
+    pub phases: Vec<i64>,
+}
+
+fn number(s: &str, line: usize) -> Result<i64, String> {
+    s.parse::<i64>().map_err(|_| format!("line {}: {} is not a number", line, s))
+}
+
+impl Circuit {
+    pub fn load(path: &str) -> Result<Circuit, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+        Circuit::parse(&contents, directory)
+    }
+
+    pub fn parse(contents: &str, directory: &Path) -> Result<Circuit, String> {
+        let mut amplifiers: Vec<Amplifier> = Vec::new();
+        let mut edges = Vec::new();
+        let mut signals = Vec::new();
+        let mut output = None;
+        let mut phases = Vec::new();
+
+        let find = |amplifiers: &Vec<Amplifier>, name: &str, line: usize| {
+            amplifiers.iter().position(|a| a.name == name)
+                .ok_or_else(|| format!("line {}: unknown amplifier {}", line, name))
+        };
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let words: Vec<&str> = line.split('#').next().unwrap().split_whitespace().collect();
+
+            match words.as_slice() {
+                [] => (),
+                ["phases", list] => {
+                    phases = list.split(',').map(|p| number(p, line_number)).collect::<Result<_, _>>()?;
+                },
+                ["amplifier", name, path] | ["amplifier", name, path, _] => {
+                    if amplifiers.iter().any(|a| a.name == *name) {
+                        return Err(format!("line {}: amplifier {} is defined twice", line_number, name));
+                    }
+
+                    let file = directory.join(path);
+                    let program = intcode::load(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+                    let phase = match words.get(3) {
+                        Some(p) => Some(number(p, line_number)?),
+                        None => None,
+                    };
+
+                    amplifiers.push(Amplifier { name: name.to_string(), program, phase });
+                },
+                [from, "->", to] => {
+                    edges.push((find(&amplifiers, from, line_number)?, find(&amplifiers, to, line_number)?));
+                },
+                ["signal", name, value] => {
+                    signals.push((find(&amplifiers, name, line_number)?, number(value, line_number)?));
+                },
+                ["output", name] => {
+                    output = Some(find(&amplifiers, name, line_number)?);
+                },
+                _ => return Err(format!("line {}: cannot understand \"{}\"", line_number, line.trim())),
+            }
+        }
+
+        let output = output.ok_or("the circuit has no output")?;
+
+        Ok(Circuit { amplifiers, edges, signals, output, phases })
+    }
+
+    // The amplifiers the output of the given amplifier is connected to.
+    fn targets(&self, amplifier: usize) -> Vec<usize> {
+        self.edges.iter().filter(|(from, _)| *from == amplifier).map(|(_, to)| *to).collect()
+    }
+
+    /// Every way to hand out distinct phase settings to the amplifiers that don't have a fixed one.
+    pub fn assignments(&self) -> Assignments<'_> {
+        Assignments::new(self)
+    }
+
+    /// The order in which the amplifiers can run one after another, if the signals never loop back and no
+    /// amplifier listens to more than one other amplifier. Each amplifier comes after the one it listens to.
+    pub fn open_loop_order(&self) -> Option<Vec<usize>> {
+        let mut sources: Vec<Option<usize>> = vec![None; self.amplifiers.len()];
+
+        for (from, to) in &self.edges {
+            if sources[*to].replace(*from).is_some() {
+                return None;
+            }
+        }
+
+        let mut order = Vec::new();
+
+        while order.len() < self.amplifiers.len() {
+            let ready = (0..self.amplifiers.len()).find(|i| {
+                !order.contains(i) && sources[*i].is_none_or(|source| order.contains(&source))
+            })?;
+
+            order.push(ready);
+        }
+
+        Some(order)
+    }
+
+    /// The amplifier each amplifier listens to, if any.
+    pub fn source(&self, amplifier: usize) -> Option<usize> {
+        self.edges.iter().find(|(_, to)| *to == amplifier).map(|(from, _)| *from)
+    }
+
+    /// Runs a single amplifier on its own with the given inputs, which have to include the phase setting.
+    /// Returns all the signals it sent before halting.
+    pub fn run_amplifier(&self, amplifier: usize, inputs: Vec<i64>) -> Result<Vec<i64>, String> {
+        let mut machine = Machine::new(&self.amplifiers[amplifier].program);
+        let mut outputs = Vec::new();
+
+        match machine.run(&mut VecDeque::from(inputs), &mut outputs) {
+            Ok(State::Halted) => Ok(outputs),
+            Ok(_) => Err(format!("amplifier {} is stuck waiting for input", self.amplifiers[amplifier].name)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Runs all amplifiers on one thread with the given phase settings and returns the signal sent to the
+    /// thrusters.
+    pub fn run(&self, phases: &[i64]) -> Result<i64, String> {
+        self.timeline(phases)?.thrusters().ok_or_else(|| self.silent())
+    }
+
+    /// Runs all amplifiers on one thread, keeping track of every signal sent along the way.
+    pub fn timeline(&self, phases: &[i64]) -> Result<Timeline, String> {
+        let mut network = self.network(phases);
+        network.record_transmissions();
+        self.finish(&mut network)?;
+
+        let mut timeline = Timeline::new(self);
+
+        for (amplifier, value) in &self.signals {
+            timeline.push(None, Some(*amplifier), *value);
+        }
+
+        for t in network.transmissions() {
+            timeline.push(Some(t.source), t.destination, t.value);
+        }
+
+        Ok(timeline)
+    }
+
+    /// Runs all amplifiers on one thread, following the phase settings and outside signals through every
+    /// signal sent along the way.
+    pub fn provenance(&self, phases: &[i64]) -> Result<Provenance, String> {
+        let mut network = self.network(phases);
+        network.record_transmissions();
+        self.finish(&mut network)?;
+
+        // Each amplifier read its phase setting, then the outside signals and then what the others sent it, in
+        // the order it was sent. Tracing it again on those inputs tells which of them went into every output.
+        let mut inputs: Vec<Vec<i64>> = phases.iter().map(|phase| vec![*phase]).collect();
+        let mut received: Vec<Vec<BTreeSet<Origin>>> =
+            (0..self.amplifiers.len()).map(|i| vec![BTreeSet::from([Origin::Phase(i)])]).collect();
+
+        for (i, (amplifier, value)) in self.signals.iter().enumerate() {
+            inputs[*amplifier].push(*value);
+            received[*amplifier].push(BTreeSet::from([Origin::Signal(i)]));
+        }
+
+        for t in network.transmissions() {
+            if let Some(to) = t.destination {
+                inputs[to].push(t.value);
+            }
+        }
+
+        let mut taints = Vec::new();
+
+        for (amplifier, inputs) in self.amplifiers.iter().zip(inputs) {
+            let mut taint = Taint::new();
+            taint.run(&mut Machine::new(&amplifier.program), &mut VecDeque::from(inputs), &mut Vec::new())
+                .map_err(|e| e.to_string())?;
+            taints.push(taint);
+        }
+
+        // An output going to several amplifiers shows up once for each of them.
+        let mut sent = vec![0; self.amplifiers.len()];
+        let mut provenance = Provenance::new(self);
+
+        for t in network.transmissions() {
+            let output = &taints[t.source].outputs()[sent[t.source] / self.targets(t.source).len().max(1)];
+            let origins: BTreeSet<Origin> =
+                output.sources.iter().flat_map(|s| received[t.source][*s].iter().cloned()).collect();
+
+            if let Some(to) = t.destination {
+                received[to].push(origins.clone());
+            }
+
+            sent[t.source] += 1;
+            provenance.push(t.source, t.destination, t.value, origins);
+        }
+
+        Ok(provenance)
+    }
+
+    fn network(&self, phases: &[i64]) -> Network {
+        let mut network = Network::new();
+
+        for (i, amplifier) in self.amplifiers.iter().enumerate() {
+            network.add(Machine::new(&amplifier.program), Route::Forward(self.targets(i)));
+            network.send(i, phases[i]).expect("The amplifier was just added");
+        }
+
+        for (amplifier, value) in &self.signals {
+            network.send(*amplifier, *value).expect("Signals go to amplifiers of the circuit");
+        }
+
+        network
+    }
+
+    fn finish(&self, network: &mut Network) -> Result<(), String> {
+        match network.run() {
+            Ok(Outcome::Halted) => Ok(()),
+            Ok(Outcome::Deadlock(waiting)) => {
+                let names: Vec<&str> = waiting.iter().map(|&i| self.amplifiers[i].name.as_str()).collect();
+                Err(format!("amplifiers {} are stuck waiting for input", names.join(", ")))
+            },
+            // Amplifiers forward every value on its own, they never send packets.
+            Ok(Outcome::Unfinished(_)) => unreachable!(),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Same as `run`, but every amplifier runs on its own thread and waits on a channel for its input. If all
+    /// amplifiers that didn't halt yet wait for a signal and none is on its way, they give up waiting.
+    pub fn run_threaded(&self, phases: &[i64]) -> Result<i64, String> {
+        let (senders, receivers): (Vec<_>, Vec<_>) = self.amplifiers.iter().map(|_| mpsc::channel()).unzip();
+        let watch = Arc::new(Mutex::new(Watch {
+            running: self.amplifiers.len(),
+            in_flight: phases.len() + self.signals.len(),
+            closed: vec![false; self.amplifiers.len()],
+            ..Watch::default()
+        }));
+
+        for (sender, phase) in senders.iter().zip(phases) {
+            sender.send(*phase).unwrap();
+        }
+
+        for (amplifier, value) in &self.signals {
+            senders[*amplifier].send(*value).unwrap();
+        }
+
+        let threads: Vec<_> = receivers.into_iter().enumerate().map(|(i, receiver)| {
+            let mut machine = Machine::new(&self.amplifiers[i].program);
+            let targets = self.targets(i).into_iter().map(|to| (to, senders[to].clone())).collect();
+            let mut input = Inbox { receiver, watch: Arc::clone(&watch) };
+            let mut output = Wire { senders: targets, watch: Arc::clone(&watch), last: None };
+
+            thread::spawn(move || {
+                let result = machine.run(&mut input, &mut output).map(|state| (state, output.last));
+
+                // Signals this amplifier didn't read anymore aren't on their way, and neither are later ones.
+                let mut watch = input.watch.lock().unwrap();
+                watch.running -= 1;
+                watch.closed[i] = true;
+                watch.in_flight -= input.receiver.try_iter().count();
+
+                result
+            })
+        }).collect();
+
+        // The amplifiers hold on to the senders of every amplifier they send signals to, so a channel never
+        // closes while an amplifier in a loop waits on it. Whether a signal is still coming is up to `Watch`.
+        drop(senders);
+
+        let mut final_output = None;
+        let mut stuck = Vec::new();
+
+        for (i, amplifier) in threads.into_iter().enumerate() {
+            match amplifier.join().expect("Amplifier thread panicked") {
+                Ok((State::Halted, last)) => if i == self.output {
+                    final_output = last;
+                },
+                Ok(_) => stuck.push(self.amplifiers[i].name.as_str()),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        if !stuck.is_empty() {
+            return Err(format!("amplifiers {} are stuck waiting for input", stuck.join(", ")));
+        }
+
+        final_output.ok_or_else(|| self.silent())
+    }
+
+    fn silent(&self) -> String {
+        format!("amplifier {} never sent a signal", self.amplifiers[self.output].name)
+    }
+}
+
+// How often an amplifier waiting for a signal checks whether the others are all waiting too.
+const PATIENCE: Duration = Duration::from_millis(10);
+
+// What the amplifier threads know about each other: how many of them are still running, how many of those wait
+// for a signal, how many signals were sent but not read yet and which amplifiers stopped reading. Once every
+// running amplifier waits and no signal is on its way, none of them will ever get one.
+#[derive(Debug, Default)]
+struct Watch {
+    running: usize,
+    waiting: usize,
+    in_flight: usize,
+    closed: Vec<bool>,
+    deadlocked: bool,
+}
+
+// The cable into an amplifier.
+struct Inbox {
+    receiver: Receiver<i64>,
+    watch: Arc<Mutex<Watch>>,
+}
+
+impl Input for Inbox {
+    fn read(&mut self) -> Option<i64> {
+        self.watch.lock().unwrap().waiting += 1;
+
+        loop {
+            let received = self.receiver.recv_timeout(PATIENCE);
+            let mut watch = self.watch.lock().unwrap();
+
+            match received {
+                Ok(value) => {
+                    watch.waiting -= 1;
+                    watch.in_flight -= 1;
+                    return Some(value);
+                },
+                Err(RecvTimeoutError::Timeout) if !watch.deadlocked
+                    && (watch.waiting < watch.running || watch.in_flight > 0) => (),
+                Err(RecvTimeoutError::Timeout) => {
+                    watch.deadlocked = true;
+                    watch.waiting -= 1;
+                    return None;
+                },
+                Err(RecvTimeoutError::Disconnected) => {
+                    watch.waiting -= 1;
+                    return None;
+                },
+            }
+        }
+    }
+}
+
+// The cables leaving an amplifier, remembering the last signal that went through them.
+struct Wire {
+    senders: Vec<(usize, Sender<i64>)>,
+    watch: Arc<Mutex<Watch>>,
+    last: Option<i64>,
+}
+
+impl Output for Wire {
+    fn write(&mut self, value: i64) {
+        self.last = Some(value);
+        // Sent while holding the watch, so a signal is counted exactly when it is in a channel.
+        let mut watch = self.watch.lock().unwrap();
+
+        for (to, sender) in &mut self.senders {
+            if !watch.closed[*to] {
+                watch.in_flight += 1;
+                sender.write(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback(signal: &str) -> Circuit {
+        let contents = format!("phases 5,6,7,8,9\n\
+            amplifier A input.txt\namplifier B input.txt\namplifier C input.txt\n\
+            amplifier D input.txt\namplifier E input.txt\n\
+            A -> B\nB -> C\nC -> D\nD -> E\nE -> A\n{}\noutput E\n", signal);
+
+        Circuit::parse(&contents, Path::new(".")).unwrap()
+    }
+
+    fn parse(contents: &str) -> Result<Circuit, String> {
+        Circuit::parse(contents, Path::new("."))
+    }
+
+    #[test]
+    fn circuits_are_parsed() {
+        let circuit = parse("# two amplifiers\nphases 1,2\n\namplifier A input.txt\n\
+            amplifier B input.txt 7 # fixed\nA -> B\nsignal A 0\noutput B\n").unwrap();
+
+        assert_eq!(circuit.phases, vec![1, 2]);
+        assert_eq!(circuit.amplifiers.iter().map(|a| a.phase).collect::<Vec<_>>(), vec![None, Some(7)]);
+        assert_eq!(circuit.edges, vec![(0, 1)]);
+        assert_eq!(circuit.signals, vec![(0, 0)]);
+        assert_eq!(circuit.output, 1);
+    }
+
+    #[test]
+    fn broken_circuits_are_rejected() {
+        assert_eq!(parse("amplifier A input.txt\namplifier A input.txt\noutput A").unwrap_err(),
+            "line 2: amplifier A is defined twice");
+        assert_eq!(parse("amplifier A input.txt\nA -> B\noutput A").unwrap_err(), "line 2: unknown amplifier B");
+        assert_eq!(parse("amplifier A input.txt\nsignal A 0").unwrap_err(), "the circuit has no output");
+        assert_eq!(parse("amplifier A input.txt\nA => A").unwrap_err(), "line 2: cannot understand \"A => A\"");
+        assert_eq!(parse("phases 1,x").unwrap_err(), "line 1: x is not a number");
+    }
+
+    #[test]
+    fn threaded_runs_give_the_same_signal() {
+        let circuit = feedback("signal A 0");
+
+        assert_eq!(circuit.run_threaded(&[9, 7, 8, 5, 6]), circuit.run(&[9, 7, 8, 5, 6]));
+    }
+
+    #[test]
+    fn threaded_runs_notice_deadlocks() {
+        let circuit = feedback("");
+        let stuck = "amplifiers A, B, C, D, E are stuck waiting for input".to_string();
+
+        assert_eq!(circuit.run(&[5, 6, 7, 8, 9]), Err(stuck.clone()));
+        assert_eq!(circuit.run_threaded(&[5, 6, 7, 8, 9]), Err(stuck));
+    }
+}
@@ -97,210 +97,71 @@
 // Try every combination of the new phase settings on the amplifier feedback loop. What is the highest signal that can be sent to the thrusters?
 //
 
-use std::fs;
-use std::collections::HashMap;
+mod circuit;
+mod provenance;
+mod search;
+mod timeline;
 
-fn pad(s: String) -> String {
-    if s.len() == 1 {
-        format!("000{}", s)
-    } else if s.len() == 3 {
-        format!("0{}", s)
-    } else {
-        s
-    }
-}
+use std::env;
+use std::fs;
 
-fn collect_parameters(s: &str, numbers: &Vec<i32>, position: usize) -> Vec<i32> {
-    let mut parameters = Vec::new();
+use circuit::Circuit;
 
-    s.chars().rev().enumerate().for_each(|(i, c)| {
-        if c == '0' {   // position mode
-            parameters.push(numbers[numbers[position + i + 1] as usize]);
-        } else {        // immediate mode
-            parameters.push(numbers[position + i + 1]);
+// Usage: day07 [--threaded] [--top N] [--timeline FILE] [--provenance] [circuit file]
+//
+// Without a circuit file the amplifiers are wired into the feedback loop from part two, see
+// circuits/feedback.txt. circuits/series.txt has the wiring from part one. With --top the N strongest signals
+// are listed together with their phase settings. With --timeline every signal sent with the best phase settings
+// is written to FILE as CSV. With --provenance every signal sent with the best phase settings is listed together
+// with the phase settings and outside signals it depends on.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut threaded = false;
+    let mut top = None;
+    let mut timeline = None;
+    let mut provenance = false;
+    let mut path = "circuits/feedback.txt".to_string();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--threaded" => threaded = true,
+            "--top" => {
+                i += 1;
+                top = Some(args.get(i).and_then(|n| n.parse::<usize>().ok()).expect("--top needs a number"));
+            },
+            "--timeline" => {
+                i += 1;
+                timeline = Some(args.get(i).expect("--timeline needs a file").clone());
+            },
+            "--provenance" => provenance = true,
+            arg => path = arg.to_string(),
         }
-    });
-
-    parameters
-}
-
-fn run(position: usize, numbers: &mut Vec<i32>, inputs: &mut Vec<i32>) -> (bool, i32, usize) {
-    match numbers.get(position).map(|x| x.to_string()) {
-        Some(x) if x.ends_with("3") => {
-            let input = inputs.pop().expect("No more inputs available");
-            let pos = numbers[position + 1] as usize;
-            std::mem::replace(&mut numbers[pos], input);
-            run(position + 2, numbers, inputs)
-        },
-        Some(x) if x.ends_with("5") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, numbers, position);
-
-            if parameters[0] != 0 {
-                run(parameters[1] as usize, numbers, inputs)
-            } else {
-                run(position + 3, numbers, inputs)
-            }
-        },
-        Some(x) if x.ends_with("6") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, numbers, position);
-
-            if parameters[0] == 0 {
-                run(parameters[1] as usize, numbers, inputs)
-            } else {
-                run(position + 3, numbers, inputs)
-            }
-        },
-        Some(x) if x.ends_with("7") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, numbers, position);
-            let pos = numbers[position + 3] as usize;
-
-            if parameters[0] < parameters[1] {
-                std::mem::replace(&mut numbers[pos], 1);
-            } else {
-                std::mem::replace(&mut numbers[pos], 0);
-            }
-
-            run(position + 4, numbers, inputs)
-        },
-        Some(x) if x.ends_with("8") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, numbers, position);
-            let pos = numbers[position + 3] as usize;
-
-            if parameters[0] == parameters[1] {
-                std::mem::replace(&mut numbers[pos], 1);
-            } else {
-                std::mem::replace(&mut numbers[pos], 0);
-            }
-
-            run(position + 4, numbers, inputs)
-        },
-        Some(x) if x.ends_with("99") => {
-            return (true, 0, 0);
-        },
-        Some(x) if x.ends_with("4") => {
-            let s = x.to_string();
-
-            let val = if s.starts_with("0") || s == "4".to_string() {
-                numbers[numbers[position + 1] as usize]
-            } else {
-                numbers[position + 1]
-            };
-
-            (false, val, position + 2)
-        },
-        Some(x) if x.ends_with("1") || x.ends_with("2") => {
-            let mut s = pad(x);
-
-            let op: String = s.drain(s.len()-2..).collect();
-            let parameters = collect_parameters(&s, numbers, position);
-
-            let pos = numbers[position + 3] as usize;
-            let a = parameters[0];
-            let b = parameters[1];
-
-            if op == "01" {     // addition
-                std::mem::replace(&mut numbers[pos], a + b);
-            } else {            // multiplication
-                std::mem::replace(&mut numbers[pos], a * b);
-            }
-
-            run(position + 4, numbers, inputs)
-        },
-        _ => {
-            panic!("This should not happen");
-        },
-    }
-}
-
-fn permutations(numbers: &mut Vec<i32>, size: usize, n: usize, output: &mut Vec<Vec<i32>>) {
-    if size == 1 {
-        output.push(numbers.clone());
+        i += 1;
     }
 
-    for i in 0..size {
-        permutations(numbers, size - 1, n, output);
+    let circuit = Circuit::load(&path).unwrap_or_else(|e| panic!("Something went wrong reading the circuit: {}", e));
+    let thruster_signals = search::search(&circuit, top.unwrap_or(1), threaded).unwrap_or_else(|e| panic!("{}", e));
 
-        if size % 2 == 1 {
-            let x = numbers[0];
-            numbers[0] = numbers[size - 1];
-            numbers[size - 1] = x;
-        } else {
-            let x = numbers[i];
-            numbers[i] = numbers[size - 1];
-            numbers[size - 1] = x;
-        }
+    if let (Some(file), Some((_, phases))) = (timeline, thruster_signals.first()) {
+        let timeline = circuit.timeline(phases).unwrap_or_else(|e| panic!("{}", e));
+        fs::write(&file, timeline.to_csv()).expect("Something went wrong writing the timeline");
     }
-}
-
-fn main() {
-    let contents = fs::read_to_string("input.txt")
-        .expect("Something went wrong reading the file");
 
-    let program: Vec<i32> = contents.split(',').map(|n| n.parse::<i32>().unwrap_or(0)).collect();
-    let mut inputs: Vec<i32>;
-    let mut thruster_signals: HashMap<String, i32> = HashMap::new();
+    let provenance = match (provenance, thruster_signals.first()) {
+        (true, Some((_, phases))) => Some(circuit.provenance(phases).unwrap_or_else(|e| panic!("{}", e))),
+        _ => None,
+    };
 
-    let mut numbers: Vec<i32> = [5, 6, 7, 8, 9].to_vec();
-    let mut perms: Vec<Vec<i32>> = Vec::new();
-    permutations(&mut numbers, 5, 5, &mut perms);
-
-    for permutation in perms {
-        let mut exits = 0;
-        let mut output = 0;
-        let mut i = 0;
-        let mut programs = Vec::new();
-        let mut positions = Vec::new();
-        let mut final_output = 0;
-        let mut exit;
-
-        loop {
-            if programs.len() < permutation.len() {
-                programs.push(program.clone());
-                positions.push(0);
-                inputs = [output.clone(), permutation[i]].to_vec();
-            } else {
-                inputs = [output.clone()].to_vec();
-            }
-
-            let t = run(positions[i % 5], &mut programs[i % 5], &mut inputs);
-            exit = t.0;
-            output = t.1;
-            positions[i % 5] = t.2;
-
-            if output != 0 {
-                final_output = output;
-            }
-
-            if exit {
-                exits += 1;
-            }
-
-            i += 1;
-
-            if exits == 5 {
-                break;
-            }
-        }
-
-        let p: String = permutation.iter().map(|i| i.to_string()).collect();
-
-        thruster_signals.insert(p, final_output);
+    match top {
+        None => println!("{:?}", thruster_signals.first().expect("There are no phase settings to try").0),
+        Some(_) => for (signal, phases) in thruster_signals {
+            let p: Vec<String> = phases.iter().map(|i| i.to_string()).collect();
+            println!("{} {}", p.join(","), signal);
+        },
     }
 
-    let mut thruster_signals_vec: Vec<(&String, &i32)> = thruster_signals.iter().collect();
-    thruster_signals_vec.sort_by(|a, b| b.1.cmp(a.1));
-
-    println!("{:?}", thruster_signals_vec[0].1);
+    if let Some(provenance) = provenance {
+        print!("{}", provenance);
+    }
 }
@@ -0,0 +1,81 @@
+// Which phase settings and outside signals each signal sent in a circuit depends on.
+//
+// Every amplifier is traced on its own, which tells which of its inputs went into each of its outputs. The
+// inputs of an amplifier are its phase setting followed by the signals it received, in order, so the origins of
+// an output are the origins of those inputs put together.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::circuit::Circuit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    /// The phase setting of an amplifier.
+    Phase(usize),
+    /// One of the signals sent into the circuit from outside, by its position in the circuit file.
+    Signal(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Traced {
+    pub source: usize,
+    /// `None` for signals that leave the circuit.
+    pub destination: Option<usize>,
+    pub value: i64,
+    pub origins: BTreeSet<Origin>,
+}
+
+#[derive(Debug)]
+pub struct Provenance {
+    names: Vec<String>,
+    signals: Vec<(usize, i64)>,
+    pub traced: Vec<Traced>,
+}
+
+impl Provenance {
+    pub fn new(circuit: &Circuit) -> Provenance {
+        Provenance {
+            names: circuit.amplifiers.iter().map(|a| a.name.clone()).collect(),
+            signals: circuit.signals.clone(),
+            traced: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, source: usize, destination: Option<usize>, value: i64, origins: BTreeSet<Origin>) {
+        self.traced.push(Traced { source, destination, value, origins });
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for signal in &self.traced {
+            let destination = signal.destination.map_or("-", |d| self.names[d].as_str());
+            let origins: Vec<String> = signal.origins.iter().map(|origin| match origin {
+                Origin::Phase(a) => format!("phase {}", self.names[*a]),
+                Origin::Signal(s) => format!("signal {} {}", self.names[self.signals[*s].0], self.signals[*s].1),
+            }).collect();
+
+            writeln!(f, "{} -> {} {}: {}", self.names[signal.source], destination, signal.value, origins.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signals_in_series_depend_on_everything_before_them() {
+        let circuit = Circuit::load("circuits/series.txt").unwrap();
+        let provenance = circuit.provenance(&[2, 3, 0, 4, 1]).unwrap();
+        let last = provenance.traced.last().unwrap();
+
+        assert_eq!(provenance.traced.len(), 5);
+        assert_eq!(provenance.traced[0].origins, BTreeSet::from([Origin::Phase(0), Origin::Signal(0)]));
+        assert_eq!((last.source, last.destination, last.value), (4, None, 366376));
+        assert_eq!(last.origins.len(), 6);
+    }
+}
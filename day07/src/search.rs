@@ -0,0 +1,318 @@
+// Finding the phase settings that send the strongest signals to the thrusters.
+//
+// Feedback loops have to be run in full for every assignment of phase settings. The assignments are generated
+// one at a time and handed out in small batches to one worker per CPU, so a worker that finishes early simply
+// takes the next batch.
+//
+// Open loops, like the amplifiers in series from part one, don't need that: all assignments that start with
+// the same phase settings share the signals of those first amplifiers. They are walked as a tree instead, so
+// every amplifier runs once per prefix rather than once per assignment.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::circuit::Circuit;
+
+const BATCH: usize = 64;
+
+/// All the ways to hand out distinct phase settings to the amplifiers without a fixed one, generated lazily.
+/// Each assignment contains the phase settings of all amplifiers, in order.
+pub struct Assignments<'a> {
+    circuit: &'a Circuit,
+    free: Vec<usize>,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<'a> Assignments<'a> {
+    pub fn new(circuit: &'a Circuit) -> Assignments<'a> {
+        let free: Vec<usize> = (0..circuit.amplifiers.len()).filter(|&i| circuit.amplifiers[i].phase.is_none()).collect();
+        let done = free.len() > circuit.phases.len();
+
+        Assignments { circuit, indices: (0..free.len()).collect(), free, done }
+    }
+
+    // Moves on to the next choice of phase indices, in lexicographic order.
+    fn advance(&mut self) -> bool {
+        let n = self.circuit.phases.len();
+
+        for slot in (0..self.indices.len()).rev() {
+            let taken = &self.indices[..slot];
+
+            if let Some(next) = (self.indices[slot] + 1..n).find(|j| !taken.contains(j)) {
+                self.indices[slot] = next;
+
+                for rest in slot + 1..self.indices.len() {
+                    let taken = &self.indices[..rest];
+                    self.indices[rest] = (0..n).find(|j| !taken.contains(j)).unwrap();
+                }
+
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl<'a> Iterator for Assignments<'a> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        if self.done {
+            return None;
+        }
+
+        let mut phases: Vec<i64> = self.circuit.amplifiers.iter().map(|a| a.phase.unwrap_or(0)).collect();
+
+        for (amplifier, index) in self.free.iter().zip(&self.indices) {
+            phases[*amplifier] = self.circuit.phases[*index];
+        }
+
+        self.done = !self.advance();
+
+        Some(phases)
+    }
+}
+
+/// Keeps the `k` strongest signals seen so far.
+struct Top {
+    k: usize,
+    heap: BinaryHeap<Reverse<(i64, Vec<i64>)>>,
+}
+
+impl Top {
+    fn new(k: usize) -> Top {
+        Top { k, heap: BinaryHeap::new() }
+    }
+
+    fn push(&mut self, signal: i64, phases: Vec<i64>) {
+        self.heap.push(Reverse((signal, phases)));
+
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    fn merge(&mut self, other: Top) {
+        for Reverse((signal, phases)) in other.heap {
+            self.push(signal, phases);
+        }
+    }
+
+    fn into_sorted(self) -> Vec<(i64, Vec<i64>)> {
+        self.heap.into_sorted_vec().into_iter().map(|Reverse(entry)| entry).collect()
+    }
+}
+
+/// Returns the `k` strongest thruster signals with the phase settings that produce them, strongest first.
+pub fn search(circuit: &Circuit, k: usize, threaded: bool) -> Result<Vec<(i64, Vec<i64>)>, String> {
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+
+    match circuit.open_loop_order() {
+        Some(order) if !threaded => search_open_loop(circuit, &order, k, workers),
+        _ => search_all(circuit, k, threaded, workers),
+    }
+}
+
+fn search_all(circuit: &Circuit, k: usize, threaded: bool, workers: usize) -> Result<Vec<(i64, Vec<i64>)>, String> {
+    let assignments = Mutex::new(circuit.assignments());
+    let failed = AtomicBool::new(false);
+
+    let results: Vec<Result<Top, String>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers).map(|_| s.spawn(|| {
+            let mut top = Top::new(k);
+
+            while !failed.load(Ordering::Relaxed) {
+                let batch: Vec<Vec<i64>> = assignments.lock().unwrap().by_ref().take(BATCH).collect();
+
+                if batch.is_empty() {
+                    break;
+                }
+
+                for phases in batch {
+                    let signal = if threaded {
+                        circuit.run_threaded(&phases)
+                    } else {
+                        circuit.run(&phases)
+                    };
+
+                    match signal {
+                        Ok(signal) => top.push(signal, phases),
+                        Err(e) => {
+                            failed.store(true, Ordering::Relaxed);
+                            return Err(e);
+                        },
+                    }
+                }
+            }
+
+            Ok(top)
+        })).collect();
+
+        handles.into_iter().map(|h| h.join().expect("Search thread panicked")).collect()
+    });
+
+    let mut top = Top::new(k);
+
+    for result in results {
+        top.merge(result?);
+    }
+
+    Ok(top.into_sorted())
+}
+
+// Walks the tree of phase settings for an open loop, running the amplifiers in `order`.
+struct Walk<'a> {
+    circuit: &'a Circuit,
+    order: &'a [usize],
+    prefix: &'a [usize],
+    used: Vec<bool>,
+    phases: Vec<i64>,
+    outputs: Vec<Vec<i64>>,
+    top: Top,
+}
+
+impl<'a> Walk<'a> {
+    fn walk(&mut self, depth: usize, free: usize) -> Result<(), String> {
+        if depth == self.order.len() {
+            let signal = *self.outputs[self.circuit.output].last().ok_or_else(|| {
+                format!("amplifier {} never sent a signal", self.circuit.amplifiers[self.circuit.output].name)
+            })?;
+
+            self.top.push(signal, self.phases.clone());
+            return Ok(());
+        }
+
+        let amplifier = self.order[depth];
+
+        if let Some(phase) = self.circuit.amplifiers[amplifier].phase {
+            return self.visit(depth, free, amplifier, phase);
+        }
+
+        for index in 0..self.circuit.phases.len() {
+            let allowed = self.prefix.get(free).is_none_or(|&p| p == index);
+
+            if allowed && !self.used[index] {
+                self.used[index] = true;
+                self.visit(depth, free + 1, amplifier, self.circuit.phases[index])?;
+                self.used[index] = false;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit(&mut self, depth: usize, free: usize, amplifier: usize, phase: i64) -> Result<(), String> {
+        let mut inputs = vec![phase];
+        inputs.extend(self.circuit.signals.iter().filter(|(a, _)| *a == amplifier).map(|(_, value)| *value));
+
+        if let Some(source) = self.circuit.source(amplifier) {
+            inputs.extend(&self.outputs[source]);
+        }
+
+        self.phases[amplifier] = phase;
+        self.outputs[amplifier] = self.circuit.run_amplifier(amplifier, inputs)?;
+        self.walk(depth + 1, free)
+    }
+}
+
+fn search_open_loop(circuit: &Circuit, order: &[usize], k: usize, workers: usize) -> Result<Vec<(i64, Vec<i64>)>, String> {
+    // The phase indices of the first two free amplifiers make up the units of work.
+    let n = circuit.phases.len();
+    let free = circuit.amplifiers.iter().filter(|a| a.phase.is_none()).count();
+    let prefixes: Vec<Vec<usize>> = match free {
+        0 => vec![vec![]],
+        1 => (0..n).map(|i| vec![i]).collect(),
+        _ => (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| vec![i, j])).collect(),
+    };
+
+    let prefixes = Mutex::new(prefixes.into_iter());
+    let failed = AtomicBool::new(false);
+
+    let results: Vec<Result<Top, String>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers).map(|_| s.spawn(|| {
+            let mut top = Top::new(k);
+
+            while !failed.load(Ordering::Relaxed) {
+                let prefix = match prefixes.lock().unwrap().next() {
+                    Some(prefix) => prefix,
+                    None => break,
+                };
+
+                let mut walk = Walk {
+                    circuit,
+                    order,
+                    prefix: &prefix,
+                    used: vec![false; n],
+                    phases: vec![0; circuit.amplifiers.len()],
+                    outputs: vec![Vec::new(); circuit.amplifiers.len()],
+                    top,
+                };
+
+                let result = walk.walk(0, 0);
+                top = walk.top;
+
+                if let Err(e) = result {
+                    failed.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+            }
+
+            Ok(top)
+        })).collect();
+
+        handles.into_iter().map(|h| h.join().expect("Search thread panicked")).collect()
+    });
+
+    let mut top = Top::new(k);
+
+    for result in results {
+        top.merge(result?);
+    }
+
+    Ok(top.into_sorted())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn assignments_skip_fixed_phases() {
+        let contents = "phases 1,2,3\namplifier A input.txt\namplifier B input.txt 7\namplifier C input.txt\noutput C";
+        let circuit = Circuit::parse(contents, Path::new(".")).unwrap();
+        let assignments: Vec<Vec<i64>> = circuit.assignments().collect();
+
+        assert_eq!(assignments, vec![
+            vec![1, 7, 2], vec![1, 7, 3], vec![2, 7, 1], vec![2, 7, 3], vec![3, 7, 1], vec![3, 7, 2],
+        ]);
+
+        let contents = "phases 1\namplifier A input.txt\namplifier B input.txt\noutput B";
+        assert_eq!(Circuit::parse(contents, Path::new(".")).unwrap().assignments().count(), 0);
+    }
+
+    #[test]
+    fn open_loops_find_what_running_every_assignment_finds() {
+        let circuit = Circuit::load("circuits/series.txt").unwrap();
+        let order = circuit.open_loop_order().unwrap();
+
+        assert_eq!(search_open_loop(&circuit, &order, 10, 3), search_all(&circuit, 10, false, 3));
+    }
+
+    #[test]
+    fn the_strongest_signals_come_first() {
+        let circuit = Circuit::load("circuits/feedback.txt").unwrap();
+
+        assert_eq!(search(&circuit, 3, false).unwrap(), vec![
+            (21596786, vec![9, 5, 8, 6, 7]),
+            (21580401, vec![9, 5, 8, 7, 6]),
+            (21563634, vec![9, 8, 5, 6, 7]),
+        ]);
+    }
+}
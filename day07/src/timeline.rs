@@ -0,0 +1,97 @@
+// Every signal sent while running a circuit, in the order they were sent.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::circuit::Circuit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    /// How many signals went through the same connection up to and including this one, so in a feedback loop
+    /// it is the number of times the signal went around.
+    pub iteration: usize,
+    /// `None` for signals sent into the circuit from outside.
+    pub source: Option<usize>,
+    /// `None` for signals that leave the circuit.
+    pub destination: Option<usize>,
+    pub value: i64,
+}
+
+#[derive(Debug)]
+pub struct Timeline {
+    names: Vec<String>,
+    output: usize,
+    sent: HashMap<(Option<usize>, Option<usize>), usize>,
+    pub signals: Vec<Signal>,
+}
+
+impl Timeline {
+    pub fn new(circuit: &Circuit) -> Timeline {
+        Timeline {
+            names: circuit.amplifiers.iter().map(|a| a.name.clone()).collect(),
+            output: circuit.output,
+            sent: HashMap::new(),
+            signals: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, source: Option<usize>, destination: Option<usize>, value: i64) {
+        let count = self.sent.entry((source, destination)).or_insert(0);
+        *count += 1;
+
+        self.signals.push(Signal { iteration: *count, source, destination, value });
+    }
+
+    /// The last signal sent by the output amplifier before it halted.
+    pub fn thrusters(&self) -> Option<i64> {
+        self.signals.iter().rev().find(|s| s.source == Some(self.output)).map(|s| s.value)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let name = |amplifier: Option<usize>| amplifier.map_or("-", |a| self.names[a].as_str());
+        let mut csv = String::from("iteration,source,destination,value\n");
+
+        for signal in &self.signals {
+            writeln!(csv, "{},{},{},{}", signal.iteration, name(signal.source), name(signal.destination), signal.value).unwrap();
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feedback_loops_go_around_until_the_amplifiers_halt() {
+        let circuit = Circuit::load("circuits/feedback.txt").unwrap();
+        let timeline = circuit.timeline(&[9, 5, 8, 6, 7]).unwrap();
+        let first = &timeline.signals[0];
+        let last = timeline.signals.last().unwrap();
+
+        assert_eq!(*first, Signal { iteration: 1, source: None, destination: Some(0), value: 0 });
+        assert_eq!((last.source, last.destination, last.value), (Some(4), Some(0), 21596786));
+        assert_eq!(last.iteration, timeline.signals.iter().filter(|s| s.source == Some(0)).count());
+        assert_eq!(timeline.thrusters(), Some(21596786));
+
+        let csv = timeline.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[..2], ["iteration,source,destination,value", "1,-,A,0"]);
+        assert_eq!(lines.len(), timeline.signals.len() + 1);
+        assert!(lines[lines.len() - 1].ends_with(",E,A,21596786"));
+    }
+
+    #[test]
+    fn the_last_signal_counts_even_if_it_is_zero() {
+        let circuit = Circuit::load("circuits/series.txt").unwrap();
+        let mut timeline = Timeline::new(&circuit);
+
+        timeline.push(Some(4), None, 12);
+        timeline.push(Some(4), None, 0);
+
+        assert_eq!(timeline.thrusters(), Some(0));
+        assert_eq!(timeline.to_csv(), "iteration,source,destination,value\n1,E,-,12\n2,E,-,0\n");
+    }
+}
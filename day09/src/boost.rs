@@ -0,0 +1,65 @@
+// What the BOOST program says in test mode. It checks every opcode in every parameter mode, outputs each one
+// that seems to be broken and finally outputs the keycode. A broken opcode is reported the way an instruction
+// would be written, with the modes in front of the opcode: 203 is an input instruction in relative mode.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Malfunction {
+    pub opcode: i64,
+    /// The mode digits ABC of the instruction, mode of the third parameter first.
+    pub modes: i64,
+}
+
+impl fmt::Display for Malfunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "opcode {} with modes {:03} is broken", self.opcode, self.modes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub malfunctions: Vec<Malfunction>,
+    pub keycode: Option<i64>,
+}
+
+impl Report {
+    /// Splits the outputs of a test run into the malfunctions and the keycode, which comes last.
+    pub fn parse(outputs: &[i64]) -> Report {
+        match outputs.split_last() {
+            Some((keycode, malfunctions)) => Report {
+                malfunctions: malfunctions.iter().map(|v| Malfunction { opcode: v % 100, modes: v / 100 }).collect(),
+                keycode: Some(*keycode),
+            },
+            None => Report { malfunctions: Vec::new(), keycode: None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_clean_run_only_outputs_the_keycode() {
+        assert_eq!(Report::parse(&[2870072642]), Report { malfunctions: Vec::new(), keycode: Some(2870072642) });
+    }
+
+    #[test]
+    fn malfunctions_come_before_the_keycode() {
+        let report = Report::parse(&[203, 1001, 9, 2870072642]);
+
+        assert_eq!(report.malfunctions, vec![
+            Malfunction { opcode: 3, modes: 2 },
+            Malfunction { opcode: 1, modes: 10 },
+            Malfunction { opcode: 9, modes: 0 },
+        ]);
+        assert_eq!(report.keycode, Some(2870072642));
+        assert_eq!(report.malfunctions[1].to_string(), "opcode 1 with modes 010 is broken");
+    }
+
+    #[test]
+    fn no_outputs_means_no_keycode() {
+        assert_eq!(Report::parse(&[]), Report { malfunctions: Vec::new(), keycode: None });
+    }
+}
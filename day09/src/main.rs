@@ -1,7 +1,3 @@
-// Sadly, this code doesn't work yet. I had a HashMap implementation that worked for the test run and resulted in a stack
-// overflow in the boost mode. I tried to change it to a Vec implementation, but end up with either a stack overflow
-// or an index that's too big for the Vec.
-//
 // --- Day 9: Sensor Boost ---
 //
 // You've just said goodbye to the rebooted rover and left Mars when you receive a faint distress signal coming from the asteroid belt. It must be the Ceres monitoring station!
@@ -52,161 +48,70 @@
 // Run the BOOST program in sensor boost mode. What are the coordinates of the distress signal?
 //
 
-use std::fs;
-
-fn pad(s: String) -> String {
-    if s.len() == 1 {
-        format!("0000{}", s)
-    } else if s.len() == 2 {
-        format!("000{}", s)
-    } else if s.len() == 3 {
-        format!("00{}", s)
-    } else if s.len() == 4 {
-        format!("0{}", s)
-    } else {
-        s
-    }
-}
-
-fn collect_parameters(s: &str, program: &mut Vec<i64>, position: usize, relative_base: usize) -> Vec<i64> {
-    s.chars().rev().enumerate().map(|(i, c)| {
-        if c == '0' {   // position mode
-            program[program[position + i + 1] as usize]
-        } else if c == '2' {
-            program[program[position + i + 1] as usize + relative_base]
-        } else {        // immediate mode
-            program[position + i + 1]
-        }
-    }).collect()
-}
-
-fn position_result(s: &str, program: &mut Vec<i64>, position_r: usize, relative_base: usize) -> usize {
-    if s.starts_with("2") {
-        program[position_r] as usize + relative_base
-    } else {
-        program[position_r] as usize
-    }
-}
-
-fn run(position: usize, program: &mut Vec<i64>, inputs: &mut Vec<i64>, relative_base: usize) {
-    println!("run {} {}", position, program.len());
-    match program[position].to_string() {
-        x if x.ends_with("3") => {
-            let input = inputs.pop().expect("No more inputs available");
-            let pos = position_result(&x, program, position + 1, relative_base);
-            program[pos] = input;
-            run(position + 2, program, inputs, relative_base);
-        },
-        x if x.ends_with("5") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, program, position, relative_base);
-
-            if parameters[0] != 0 {
-                run(parameters[1] as usize, program, inputs, relative_base);
-            } else {
-                run(position + 3, program, inputs, relative_base);
-            }
-        },
-        x if x.ends_with("6") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, program, position, relative_base);
+mod boost;
 
-            if parameters[0] == 0 {
-                run(parameters[1] as usize, program, inputs, relative_base);
-            } else {
-                run(position + 3, program, inputs, relative_base);
-            }
-        },
-        x if x.ends_with("7") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
+use std::env;
+use std::process;
 
-            let parameters = collect_parameters(&s, program, position, relative_base);
-            let pos = position_result(&s, program, position + 3, relative_base);
+use intcode::{engines, Engine, Execution, State};
 
-            if parameters[0] < parameters[1] {
-                program[pos] = 1;
-            } else {
-                program[pos] = 0;
-            }
+use boost::Report;
 
-            run(position + 4, program, inputs, relative_base);
-        },
-        x if x.ends_with("8") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-
-            let parameters = collect_parameters(&s, program, position, relative_base);
-            let pos = position_result(&s, program, position + 3, relative_base);
-
-            if parameters[0] == parameters[1] {
-                program[pos] = 1;
-            } else {
-                program[pos] = 0;
-            }
-
-            run(position + 4, program, inputs, relative_base);
-        },
-        x if x.ends_with("99") => {
-            return;
-        },
-        x if x.ends_with("9") => {
-            let mut s = pad(x);
-            let _ = s.drain(s.len()-2..);
-            let parameters = collect_parameters(&s, program, position, relative_base);
-            run(position + 2, program, inputs, relative_base + parameters[0] as usize);
-        },
-        x if x.ends_with("4") => {
-            let s = x.to_string();
+// Usage: day09 [--engine NAME]
+//
+// Runs the BOOST program in test mode and in sensor boost mode. Any malfunctions reported in test mode are
+// listed and make the exit code 1, as does a run that doesn't halt, so this doubles as an acceptance check for
+// the engines in the intcode crate. --engine picks one of them, the interpreter by default.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut name = "interpreter".to_string();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--engine" => {
+                i += 1;
+                name = args.get(i).expect("--engine needs a name").clone();
+            },
+            arg => panic!("Unknown argument {}", arg),
+        }
+        i += 1;
+    }
 
-            let val = if s.starts_with("0") || s == "4".to_string() {
-                program[program[position + 1] as usize]
-            } else if s.starts_with("2") {
-                program[program[position + 1] as usize + relative_base]
-            } else {
-                program[position + 1]
-            };
+    let engine = engines().into_iter().find(|e| e.name() == name).unwrap_or_else(|| panic!("Unknown engine {}", name));
+    let program: Vec<i64> = intcode::load("input.txt")
+        .unwrap_or_else(|e| panic!("Something went wrong reading the file: {}", e));
+    let mut failed = false;
 
-            println!("OUTPUT {:?}", val);
+    let test = run(engine.as_ref(), &program, 1);
+    let report = Report::parse(&test.outputs);
+    failed |= test.result != Ok(State::Halted);
 
-            run(position + 2, program, inputs, relative_base);
-        },
-        x if x.ends_with("1") || x.ends_with("2") => {
-            let mut s = pad(x);
+    for malfunction in &report.malfunctions {
+        println!("{}", malfunction);
+    }
+    failed |= !report.malfunctions.is_empty();
 
-            let op: String = s.drain(s.len()-2..).collect();
-            let parameters = collect_parameters(&s, program, position, relative_base);
+    match (&test.result, report.keycode) {
+        (Ok(State::Halted), Some(keycode)) => println!("keycode {}", keycode),
+        (Ok(State::Halted), None) => println!("test mode halted without a keycode"),
+        (result, _) => println!("test mode stopped: {:?}", result),
+    }
 
-            let a = parameters[0];
-            let b = parameters[1];
-            let pos = position_result(&s, program, position + 3, relative_base);
+    let boost = run(engine.as_ref(), &program, 2);
+    failed |= boost.result != Ok(State::Halted) || boost.outputs.len() != 1;
 
-            if op == "01" {     // addition
-                program[pos] = a + b;
-            } else {            // multiplication
-                program[pos] = a * b;
-            }
+    match (&boost.result, boost.outputs.as_slice()) {
+        (Ok(State::Halted), [coordinates]) => println!("coordinates {}", coordinates),
+        (Ok(State::Halted), outputs) => println!("sensor boost mode output {:?}", outputs),
+        (result, _) => println!("sensor boost mode stopped: {:?}", result),
+    }
 
-            run(position + 4, program, inputs, relative_base);
-        },
-        _ => {
-            panic!("This should not happen");
-        },
+    if failed {
+        process::exit(1);
     }
 }
 
-fn main() {
-    let contents = fs::read_to_string("input.txt")
-        .expect("Something went wrong reading the file");
-
-    let mut program: Vec<i64> = contents.split(',').map(|n| n.parse::<i64>().unwrap_or(0)).collect();
-    program.resize(32768, 0);
-
-    let mut inputs: Vec<i64> = [1].to_vec();
-
-    run(0, &mut program, &mut inputs, 0);
+fn run(engine: &dyn Engine, program: &[i64], mode: i64) -> Execution {
+    engine.execute(program, &[mode], usize::MAX)
 }
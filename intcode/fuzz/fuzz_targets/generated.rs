@@ -0,0 +1,23 @@
+// Runs programs made up by the generator on every engine.
+//
+//     cargo +nightly fuzz run generated
+
+#![no_main]
+
+use intcode::{differential, generator};
+use intcode::{engines, MEMORY_LIMIT};
+use libfuzzer_sys::fuzz_target;
+
+const BUDGET: usize = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let generated = generator::generate(data);
+    let engines = engines();
+    let reference = engines[0].execute(&generated.program, &generated.inputs, BUDGET);
+
+    assert!(reference.memory.len() <= MEMORY_LIMIT);
+
+    if let Some(mismatch) = differential::compare(&engines, &generated.program, &generated.inputs, BUDGET) {
+        panic!("{:#?}", differential::minimize(&engines, mismatch, BUDGET));
+    }
+});
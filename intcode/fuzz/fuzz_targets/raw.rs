@@ -0,0 +1,20 @@
+// Runs the input bytes as they are, every 8 bytes making up one value of the program.
+//
+//     cargo +nightly fuzz run raw
+
+#![no_main]
+
+use intcode::{Engine, Interpreter, MEMORY_LIMIT};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let program: Vec<i64> = data.chunks(8).map(|chunk| {
+        let mut word = [0; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        i64::from_le_bytes(word)
+    }).collect();
+
+    let execution = Interpreter.execute(&program, &[0, 1, -1], 10_000);
+
+    assert!(execution.memory.len() <= MEMORY_LIMIT);
+});
@@ -0,0 +1,74 @@
+// Many programs talk in ASCII: they read lines of text one character at a time, each line ending in a 10, and
+// print text the same way. Values that aren't ASCII are results, like the amount of dust collected.
+
+use std::collections::VecDeque;
+
+use crate::io::{Input, Output};
+
+/// Feeds lines of text to a machine.
+#[derive(Debug, Default)]
+pub struct AsciiInput {
+    queue: VecDeque<i64>,
+}
+
+impl AsciiInput {
+    pub fn new() -> AsciiInput {
+        AsciiInput::default()
+    }
+
+    /// Queues a line, followed by the 10 that ends it. A line break at the end of `line` is dropped first.
+    pub fn push_line(&mut self, line: &str) {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        self.queue.extend(line.bytes().map(|b| b as i64));
+        self.queue.push_back(10);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl Input for AsciiInput {
+    fn read(&mut self) -> Option<i64> {
+        self.queue.pop_front()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ascii {
+    Text(String),
+    Value(i64),
+}
+
+/// Collects what a machine prints. Consecutive characters end up in the same `Ascii::Text`.
+#[derive(Debug, Default)]
+pub struct AsciiOutput {
+    collected: Vec<Ascii>,
+}
+
+impl AsciiOutput {
+    pub fn new() -> AsciiOutput {
+        AsciiOutput::default()
+    }
+
+    /// Everything printed since the last call.
+    pub fn take(&mut self) -> Vec<Ascii> {
+        std::mem::take(&mut self.collected)
+    }
+}
+
+impl Output for AsciiOutput {
+    fn write(&mut self, value: i64) {
+        if (0..=127).contains(&value) {
+            if let Some(Ascii::Text(text)) = self.collected.last_mut() {
+                text.push(value as u8 as char);
+            } else {
+                self.collected.push(Ascii::Text((value as u8 as char).to_string()));
+            }
+        } else {
+            self.collected.push(Ascii::Value(value));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// Turning text back into programs. The syntax is the one the disassembler prints, one instruction per line:
+//
+//     # prints the input twice
+//     start:  in [value]
+//             out [value]
+//             out [value]
+//             halt
+//     value:  data 0
+//
+// Parameters are `[12]` in position mode, `12` in immediate mode and `[rb+12]` or `[rb-12]` in relative mode.
+// Labels stand for the address they are defined at, both as `value` and `[value]`. A number followed by a colon
+// at the start of a line, like the addresses in the disassembler's output, is ignored.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::instructions::{InstructionSet, Role};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleError {
+    UnknownMnemonic { line: usize, mnemonic: String },
+    ParameterCount { line: usize, expected: usize, found: usize },
+    BadParameter { line: usize, parameter: String },
+    ImmediateWrite { line: usize },
+    UnknownLabel { line: usize, label: String },
+    DuplicateLabel { line: usize, label: String },
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssembleError::UnknownMnemonic { line, mnemonic } => write!(f, "line {}: unknown instruction {}", line, mnemonic),
+            AssembleError::ParameterCount { line, expected, found } => {
+                write!(f, "line {}: expected {} parameters, found {}", line, expected, found)
+            },
+            AssembleError::BadParameter { line, parameter } => {
+                write!(f, "line {}: cannot understand parameter \"{}\"", line, parameter)
+            },
+            AssembleError::ImmediateWrite { line } => write!(f, "line {}: write in immediate mode", line),
+            AssembleError::UnknownLabel { line, label } => write!(f, "line {}: unknown label {}", line, label),
+            AssembleError::DuplicateLabel { line, label } => write!(f, "line {}: label {} is defined twice", line, label),
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+// A number or a label, before labels are known.
+enum Value<'a> {
+    Number(i64),
+    Label(&'a str),
+}
+
+fn value(text: &str, line: usize) -> Result<Value<'_>, AssembleError> {
+    if let Ok(number) = text.parse::<i64>() {
+        Ok(Value::Number(number))
+    } else if is_label(text) {
+        Ok(Value::Label(text))
+    } else {
+        Err(AssembleError::BadParameter { line, parameter: text.to_string() })
+    }
+}
+
+fn is_label(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// A parameter's mode and value.
+fn parameter(text: &str, line: usize) -> Result<(i64, Value<'_>), AssembleError> {
+    match text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        Some(inner) => match inner.strip_prefix("rb") {
+            Some(offset) if offset.starts_with('+') => Ok((2, value(&offset[1..], line)?)),
+            Some(offset) if offset.starts_with('-') => Ok((2, value(offset, line)?)),
+            Some("") => Ok((2, Value::Number(0))),
+            _ => Ok((0, value(inner, line)?)),
+        },
+        None => Ok((1, value(text, line)?)),
+    }
+}
+
+struct Statement<'a> {
+    line: usize,
+    mnemonic: &'a str,
+    parameters: Vec<&'a str>,
+}
+
+/// Assembles a program using the given instructions.
+pub fn assemble(instructions: &InstructionSet, text: &str) -> Result<Vec<i64>, AssembleError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = 0;
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let mut rest = line.split('#').next().unwrap().trim();
+
+        while let Some(colon) = rest.find(':') {
+            let label = rest[..colon].trim();
+
+            if label.parse::<usize>().is_err() {
+                if !is_label(label) {
+                    break;
+                }
+
+                if labels.insert(label, address as i64).is_some() {
+                    return Err(AssembleError::DuplicateLabel { line: number, label: label.to_string() });
+                }
+            }
+
+            rest = rest[colon + 1..].trim();
+        }
+
+        if rest.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, parameters) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+        let parameters: Vec<&str> = parameters.split(',').map(str::trim).filter(|p| !p.is_empty()).collect();
+
+        address += match mnemonic {
+            "data" => parameters.len(),
+            _ => {
+                let definition = instructions.by_mnemonic(mnemonic)
+                    .ok_or_else(|| AssembleError::UnknownMnemonic { line: number, mnemonic: mnemonic.to_string() })?;
+
+                if definition.roles.len() != parameters.len() {
+                    return Err(AssembleError::ParameterCount { line: number, expected: definition.roles.len(), found: parameters.len() });
+                }
+
+                parameters.len() + 1
+            },
+        };
+
+        statements.push(Statement { line: number, mnemonic, parameters });
+    }
+
+    let resolve = |value: Value, line: usize| match value {
+        Value::Number(number) => Ok(number),
+        Value::Label(label) => labels.get(label).cloned()
+            .ok_or_else(|| AssembleError::UnknownLabel { line, label: label.to_string() }),
+    };
+
+    let mut program = Vec::new();
+
+    for statement in statements {
+        let line = statement.line;
+
+        if statement.mnemonic == "data" {
+            for text in statement.parameters {
+                program.push(resolve(value(text, line)?, line)?);
+            }
+            continue;
+        }
+
+        let definition = instructions.by_mnemonic(statement.mnemonic).unwrap();
+        let mut instruction = definition.opcode;
+        let mut values = Vec::new();
+
+        for (n, (text, role)) in statement.parameters.iter().zip(&definition.roles).enumerate() {
+            let (mode, value) = parameter(text, line)?;
+
+            if mode == 1 && *role == Role::Write {
+                return Err(AssembleError::ImmediateWrite { line });
+            }
+
+            instruction += mode * 10_i64.pow(n as u32 + 2);
+            values.push(resolve(value, line)?);
+        }
+
+        program.push(instruction);
+        program.extend(values);
+    }
+
+    Ok(program)
+}
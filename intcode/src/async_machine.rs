@@ -0,0 +1,223 @@
+// Machines that run as futures, so many of them can share a single thread. Instead of giving up when there is
+// no input, an `AsyncIntcode` waits for its input the way any other future waits, and lets the executor run
+// something else in the meantime.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::machine::{Error, Machine, State};
+
+pub trait AsyncInput {
+    /// Resolves to the next input value, or to `None` once no more values are going to arrive.
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>>;
+}
+
+pub trait AsyncOutput {
+    fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()>;
+}
+
+impl<T: AsyncInput + ?Sized> AsyncInput for &mut T {
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>> {
+        (**self).poll_read(cx)
+    }
+}
+
+impl<T: AsyncOutput + ?Sized> AsyncOutput for &mut T {
+    fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()> {
+        (**self).poll_write(cx, value)
+    }
+}
+
+#[derive(Debug)]
+pub struct AsyncIntcode {
+    machine: Machine,
+}
+
+impl AsyncIntcode {
+    pub fn new(machine: Machine) -> AsyncIntcode {
+        AsyncIntcode { machine }
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    pub fn into_inner(self) -> Machine {
+        self.machine
+    }
+
+    /// Runs until the program halts. Returns `State::WaitingForInput` if the program wants more input after
+    /// the input was closed.
+    pub async fn run<I, O>(&mut self, input: I, output: O) -> Result<State, Error>
+    where
+        I: AsyncInput,
+        O: AsyncOutput,
+    {
+        self.run_for(input, output, usize::MAX).await
+    }
+
+    /// Like `run`, but gives up with `State::Running` after executing `budget` instructions. Waiting for input
+    /// doesn't count.
+    pub async fn run_for<I, O>(&mut self, mut input: I, mut output: O, budget: usize) -> Result<State, Error>
+    where
+        I: AsyncInput,
+        O: AsyncOutput,
+    {
+        let mut next: Option<i64> = None;
+        let mut outputs: Vec<i64> = Vec::new();
+        let mut steps = 0;
+
+        loop {
+            if steps == budget {
+                return Ok(State::Running);
+            }
+
+            let state = self.machine.step(&mut next, &mut outputs)?;
+
+            for value in outputs.drain(..) {
+                poll_fn(|cx| output.poll_write(cx, value)).await;
+            }
+
+            match state {
+                State::Running => steps += 1,
+                State::Halted => return Ok(State::Halted),
+                State::WaitingForInput => match poll_fn(|cx| input.poll_read(cx)).await {
+                    Some(value) => next = Some(value),
+                    None => return Ok(State::WaitingForInput),
+                },
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    queue: VecDeque<i64>,
+    waker: Option<Waker>,
+    senders: usize,
+}
+
+/// The sending half of a channel between machines on the same thread. Sending never has to wait.
+#[derive(Debug)]
+pub struct Sender(Rc<RefCell<Shared>>);
+
+/// The receiving half of a channel. It is closed once all its senders are dropped and the queue is empty.
+#[derive(Debug)]
+pub struct Receiver(Rc<RefCell<Shared>>);
+
+pub fn channel() -> (Sender, Receiver) {
+    let shared = Rc::new(RefCell::new(Shared { queue: VecDeque::new(), waker: None, senders: 1 }));
+    (Sender(shared.clone()), Receiver(shared))
+}
+
+impl Sender {
+    pub fn send(&self, value: i64) {
+        let mut shared = self.0.borrow_mut();
+        shared.queue.push_back(value);
+
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Sender {
+        self.0.borrow_mut().senders += 1;
+        Sender(self.0.clone())
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut shared = self.0.borrow_mut();
+        shared.senders -= 1;
+
+        if shared.senders == 0 {
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl AsyncOutput for Sender {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.send(value);
+        Poll::Ready(())
+    }
+}
+
+impl AsyncOutput for Vec<i64> {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.push(value);
+        Poll::Ready(())
+    }
+}
+
+impl AsyncInput for Receiver {
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>> {
+        let mut shared = self.0.borrow_mut();
+
+        match shared.queue.pop_front() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if shared.senders == 0 => Poll::Ready(None),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+/// A future that resolves to the results of all the given futures, in the same order, once all of them are
+/// done. They all make progress concurrently on the thread polling it.
+pub fn join_all<'a, T: 'a>(futures: Vec<Pin<Box<dyn Future<Output = T> + 'a>>>) -> impl Future<Output = Vec<T>> + 'a {
+    let mut futures: Vec<_> = futures.into_iter().map(Some).collect();
+    let mut results: Vec<Option<T>> = futures.iter().map(|_| None).collect();
+
+    poll_fn(move |cx| {
+        for (future, result) in futures.iter_mut().zip(results.iter_mut()) {
+            if let Some(f) = future {
+                if let Poll::Ready(value) = f.as_mut().poll(cx) {
+                    *result = Some(value);
+                    *future = None;
+                }
+            }
+        }
+
+        if futures.iter().all(Option::is_none) {
+            Poll::Ready(results.iter_mut().map(|r| r.take().unwrap()).collect())
+        } else {
+            Poll::Pending
+        }
+    })
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs a future to completion on the current thread, parking the thread whenever there is nothing to do.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
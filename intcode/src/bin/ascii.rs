@@ -0,0 +1,208 @@
+// Plays an ASCII program in the terminal: whatever it prints is shown, and every line typed in is sent to it.
+// Values that aren't ASCII are shown on a line of their own, in brackets.
+//
+// Usage: ascii [--grid] [--transcript FILE] [--record FILE] [--replay FILE [--stop N]] PROGRAM
+//
+// With --grid the program draws `x, y, tile` triples instead, like the arcade cabinet. The canvas is shown every
+// time the program waits for input, and typed lines are values separated by commas. With --transcript everything
+// shown and typed in is also written to FILE, with typed lines starting with "> ". --record saves every value sent
+// to the program as a session, even if the program fails, which --replay plays back before anything typed in,
+// checking that the program still prints the same. What the program printed during the replay is shown before
+// reading the next line. With --stop the replay ends right before the program reads value N, and shows where it
+// is and the instructions it runs next.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use intcode::ascii::{Ascii, AsciiInput, AsciiOutput};
+use intcode::disassembler::{self, Line};
+use intcode::grid::{Frames, Grid, Palette};
+use intcode::session::{self, Recorder, Session};
+use intcode::{Machine, Output, State};
+
+const USAGE: &str = "Usage: ascii [--grid] [--transcript FILE] [--record FILE] [--replay FILE [--stop N]] PROGRAM";
+
+// How many instructions are shown where a replay stopped.
+const LOOKAHEAD: usize = 5;
+
+// Shows where a machine is and the instructions it runs next, as far as they can be decoded.
+fn show(machine: &Machine, inputs: usize) {
+    let mut address = machine.ip();
+
+    eprintln!("stopped before input {} at address {}, relative base {}", inputs, address, machine.relative_base());
+
+    for _ in 0..LOOKAHEAD {
+        match disassembler::decode(machine.instructions(), machine.memory(), address) {
+            Some(instruction) => {
+                address += instruction.size();
+                eprintln!("{}", Line::Instruction(instruction));
+            },
+            None => {
+                eprintln!("{}", Line::Data { address, value: machine.get(address) });
+                break;
+            },
+        }
+    }
+}
+
+fn save(record: &Option<String>, recorder: &Recorder) {
+    if let Some(file) = record {
+        fs::write(file, recorder.session().to_string()).expect("Something went wrong writing the session");
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut drawing = false;
+    let mut transcript = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut stop = None;
+    let mut path = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--grid" => drawing = true,
+            "--transcript" => {
+                i += 1;
+                let file = args.get(i).expect("--transcript needs a file");
+                transcript = Some(File::create(file).expect("Something went wrong creating the transcript"));
+            },
+            "--record" => {
+                i += 1;
+                record = Some(args.get(i).expect("--record needs a file").to_string());
+            },
+            "--replay" => {
+                i += 1;
+                let file = args.get(i).expect("--replay needs a file");
+                let contents = fs::read_to_string(file).expect("Something went wrong reading the session");
+                replay = Some(Session::parse(&contents).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", file, e);
+                    process::exit(1);
+                }));
+            },
+            "--stop" => {
+                i += 1;
+                stop = Some(args.get(i).and_then(|n| n.parse::<usize>().ok()).expect("--stop needs a number"));
+            },
+            arg => path = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let path = path.unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let program = intcode::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut input = AsciiInput::new();
+    let mut output = AsciiOutput::new();
+    let mut values = VecDeque::new();
+    let mut grid = Grid::new(Palette::default()).with_frames(Frames::Terminal);
+
+    let (mut machine, mut recorder) = match &replay {
+        Some(session) => {
+            let replayed = session::replay(&program, session, stop).unwrap_or_else(|e| {
+                eprintln!("replay failed: {}", e);
+                process::exit(1);
+            });
+
+            if stop.is_some() && replayed.inputs < session.entries.len() {
+                show(&replayed.machine, replayed.inputs);
+            }
+
+            // Shown with whatever the program prints next, so the prompt for the next line isn't lost.
+            for value in &replayed.outputs {
+                match drawing {
+                    true => grid.write(*value),
+                    false => output.write(*value),
+                }
+            }
+
+            // A recording made after a replay starts with the replayed part, so it can be replayed on its own.
+            let recorder = Recorder::resume(session.prefix(replayed.inputs));
+            (replayed.machine, recorder)
+        },
+        None => (Machine::new(&program), Recorder::new()),
+    };
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        let state = match drawing {
+            true => recorder.run(&mut machine, &mut values, &mut grid),
+            false => recorder.run(&mut machine, &mut input, &mut output),
+        };
+        let state = state.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            save(&record, &recorder);
+            process::exit(1);
+        });
+
+        let mut shown = String::new();
+
+        if drawing {
+            grid.frame().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+
+            // The frame is on the terminal already, the transcript gets a copy.
+            if transcript.is_some() {
+                shown = grid.render().unwrap_or_default();
+            }
+        } else {
+            for chunk in output.take() {
+                match chunk {
+                    Ascii::Text(text) => shown.push_str(&text),
+                    Ascii::Value(value) => {
+                        if !shown.is_empty() && !shown.ends_with('\n') {
+                            shown.push('\n');
+                        }
+                        shown.push_str(&format!("[{}]\n", value));
+                    },
+                }
+            }
+
+            print!("{}", shown);
+            io::stdout().flush().unwrap();
+        }
+
+        if let Some(file) = &mut transcript {
+            file.write_all(shown.as_bytes()).expect("Something went wrong writing the transcript");
+        }
+
+        if state == State::Halted {
+            break;
+        }
+
+        let line = match lines.next() {
+            Some(line) => line.expect("Something went wrong reading the input"),
+            None => break,
+        };
+
+        if let Some(file) = &mut transcript {
+            writeln!(file, "> {}", line).expect("Something went wrong writing the transcript");
+        }
+
+        if drawing {
+            match intcode::loader::parse(&line) {
+                Ok(line) => values.extend(line),
+                Err(e) => eprintln!("{}", e),
+            }
+        } else {
+            input.push_line(&line);
+        }
+    }
+
+    save(&record, &recorder);
+}
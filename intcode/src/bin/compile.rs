@@ -0,0 +1,37 @@
+// Compiles a program in the small language from `intcode::compiler` and prints it, ready to be loaded.
+//
+// Usage: compile [--assembly] SOURCE
+//
+// With --assembly the generated assembly is printed instead.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use intcode::compiler;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let assembly = args.iter().any(|arg| arg == "--assembly");
+    let path = args.iter().find(|arg| !arg.starts_with("--")).unwrap_or_else(|| {
+        eprintln!("Usage: compile [--assembly] SOURCE");
+        process::exit(2);
+    });
+
+    let source = fs::read_to_string(path).expect("Something went wrong reading the file");
+    let compiled = if assembly {
+        compiler::compile_to_assembly(&source)
+    } else {
+        compiler::compile(&source).map(|program| {
+            program.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",") + "\n"
+        })
+    };
+
+    match compiled {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        },
+    }
+}
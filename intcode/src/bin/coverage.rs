@@ -0,0 +1,63 @@
+// Runs a program and shows which of its instructions ran how often, and which way the branches went.
+//
+// Usage: coverage [--input VALUES] PROGRAM
+//
+// --input takes the values to send to the program, separated by commas.
+
+use std::collections::VecDeque;
+use std::env;
+use std::process;
+
+use intcode::coverage::Coverage;
+use intcode::{Machine, State};
+
+const USAGE: &str = "Usage: coverage [--input VALUES] PROGRAM";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut inputs = VecDeque::new();
+    let mut path = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--input" => {
+                i += 1;
+                inputs = match args.get(i).map(|s| intcode::loader::parse(s)) {
+                    Some(Ok(values)) => values.into_iter().collect(),
+                    _ => usage(),
+                };
+            },
+            arg => path = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let path = path.unwrap_or_else(|| usage());
+    let program = intcode::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut machine = Machine::new(&program);
+    let mut coverage = Coverage::new();
+    let mut outputs = Vec::new();
+    let result = coverage.run(&mut machine, &mut inputs, &mut outputs);
+
+    print!("{}", coverage.report(&program));
+    println!("output {:?}", outputs);
+
+    match result {
+        Ok(State::Halted) => (),
+        Ok(_) => println!("stopped waiting for input at {}", machine.ip()),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,63 @@
+// Prints a program as pseudo-code.
+//
+// Usage: decompile [--input VALUES] PROGRAM
+//
+// With --input the program runs with the given values first, separated by commas, and what it looked like
+// after running is decompiled instead. That also shows code that is only reached through computed jumps, or that
+// the program changes before running it.
+
+use std::collections::VecDeque;
+use std::env;
+use std::process;
+
+use intcode::coverage::Coverage;
+use intcode::{decompiler, Machine};
+
+const USAGE: &str = "Usage: decompile [--input VALUES] PROGRAM";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut inputs = None;
+    let mut path = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--input" => {
+                i += 1;
+                inputs = match args.get(i).map(|s| intcode::loader::parse(s)) {
+                    Some(Ok(values)) => Some(values.into_iter().collect::<VecDeque<_>>()),
+                    _ => usage(),
+                };
+            },
+            arg => path = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let path = path.unwrap_or_else(|| usage());
+    let program = intcode::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    match inputs {
+        Some(mut inputs) => {
+            let mut machine = Machine::new(&program);
+            let mut coverage = Coverage::new();
+
+            if let Err(e) = coverage.run(&mut machine, &mut inputs, &mut Vec::new()) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+
+            print!("{}", decompiler::decompile_traced(machine.memory(), &coverage.executed().collect()));
+        },
+        None => print!("{}", decompiler::decompile(&program)),
+    }
+}
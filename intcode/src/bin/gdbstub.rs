@@ -0,0 +1,72 @@
+// Waits for GDB to attach to a machine running a program, see the gdbstub module for what it supports.
+//
+// Usage: gdbstub [--port N] [--input VALUES] PROGRAM
+//
+// The stub listens on localhost, port 1234 unless --port says otherwise, and exits once the debugger is done.
+// --input takes the values to send to the program, separated by commas. The target description GDB needs is
+// printed with --target-xml.
+
+use std::collections::VecDeque;
+use std::env;
+use std::net::TcpListener;
+use std::process;
+
+use intcode::gdbstub::{Stub, TARGET_XML};
+use intcode::Machine;
+
+const USAGE: &str = "Usage: gdbstub [--port N] [--input VALUES] PROGRAM\n       gdbstub --target-xml";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut port = 1234;
+    let mut inputs = VecDeque::new();
+    let mut path = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--port" => {
+                i += 1;
+                port = args.get(i).and_then(|n| n.parse::<u16>().ok()).unwrap_or_else(|| usage());
+            },
+            "--input" => {
+                i += 1;
+                inputs = match args.get(i).map(|s| intcode::loader::parse(s)) {
+                    Some(Ok(values)) => values.into_iter().collect(),
+                    _ => usage(),
+                };
+            },
+            "--target-xml" => {
+                print!("{}", TARGET_XML);
+                return;
+            },
+            arg => path = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let path = path.unwrap_or_else(|| usage());
+    let program = intcode::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut stub = Stub::new(Machine::new(&program)).with_input(inputs);
+
+    let result = TcpListener::bind(("127.0.0.1", port)).and_then(|listener| {
+        eprintln!("waiting for a debugger on {}", listener.local_addr()?);
+        let (stream, address) = listener.accept()?;
+        eprintln!("debugger attached from {}", address);
+        stub.serve(stream)
+    });
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,89 @@
+// Runs a program and shows which parts of its memory changed, with the address of the instruction that wrote
+// each changed cell last.
+//
+// Usage: heapdiff [--set ADDRESS=VALUE]... [--input VALUES] PROGRAM
+//
+// --set changes a cell before the run, like the noun and verb on day 2, and is not part of the difference.
+// --input takes the values to send to the program, separated by commas.
+
+use std::collections::VecDeque;
+use std::env;
+use std::process;
+
+use intcode::heap::{self, Writers};
+use intcode::{Machine, State};
+
+const USAGE: &str = "Usage: heapdiff [--set ADDRESS=VALUE]... [--input VALUES] PROGRAM";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut sets = Vec::new();
+    let mut inputs = VecDeque::new();
+    let mut path = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--set" => {
+                i += 1;
+                let set = args.get(i).and_then(|s| {
+                    let mut parts = s.splitn(2, '=');
+                    Some((parts.next()?.parse::<usize>().ok()?, parts.next()?.parse::<i64>().ok()?))
+                });
+                sets.push(set.unwrap_or_else(|| usage()));
+            },
+            "--input" => {
+                i += 1;
+                let values = args.get(i).map(|s| intcode::loader::parse(s));
+                inputs = match values {
+                    Some(Ok(values)) => values.into_iter().collect(),
+                    _ => usage(),
+                };
+            },
+            arg => path = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let path = path.unwrap_or_else(|| usage());
+    let program = intcode::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut machine = Machine::new(&program);
+
+    for (address, value) in sets {
+        machine.set(address, value).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
+    }
+
+    let before = machine.memory().to_vec();
+    let mut writers = Writers::new();
+    let mut outputs = Vec::new();
+    let result = writers.run(&mut machine, &mut inputs, &mut outputs);
+
+    for region in heap::diff(&before, machine.memory(), Some(&writers)) {
+        print!("{}", region);
+    }
+
+    if !outputs.is_empty() {
+        println!("output {:?}", outputs);
+    }
+
+    match result {
+        Ok(State::Halted) => (),
+        Ok(_) => println!("stopped waiting for input at {}", machine.ip()),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,208 @@
+// Runs any Intcode program.
+//
+// Usage: intcode [--input VALUES] [--input-file FILE] [--interactive] [--set ADDRESS=VALUE]...
+//                [--format lines|csv|ascii] [--steps N] [--print ADDRESSES] PROGRAM
+//
+// --input takes values separated by commas and --input-file reads them from a file, both can be given more than
+// once and are sent in order. With --interactive the program reads from stdin once those are used up, a line of
+// values separated by commas at a time, or a line of text with --format ascii. --set changes a cell before the
+// run, like the noun and verb on day 2, as long as the address is below the memory limit.
+//
+// Outputs are printed one per line, separated by commas with --format csv, or as text with --format ascii, where
+// values that aren't ASCII are shown on a line of their own, in brackets. --steps stops the program after
+// executing that many instructions, and --print shows the cells at the given addresses, separated by commas,
+// once the program stopped.
+
+use std::collections::VecDeque;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use intcode::{Input, Machine, Output, State};
+
+const USAGE: &str = "Usage: intcode [--input VALUES] [--input-file FILE] [--interactive] [--set ADDRESS=VALUE]... \
+[--format lines|csv|ascii] [--steps N] [--print ADDRESSES] PROGRAM";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Lines,
+    Csv,
+    Ascii,
+}
+
+// The values given up front, followed by whatever is typed in if interactive.
+struct Inputs {
+    queue: VecDeque<i64>,
+    interactive: bool,
+    format: Format,
+}
+
+impl Input for Inputs {
+    fn read(&mut self) -> Option<i64> {
+        while self.queue.is_empty() && self.interactive {
+            io::stdout().flush().expect("Something went wrong writing the output");
+
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line).expect("Something went wrong reading the input") == 0 {
+                self.interactive = false;
+                break;
+            }
+
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+
+            match self.format {
+                Format::Ascii => {
+                    self.queue.extend(line.bytes().map(|b| b as i64));
+                    self.queue.push_back(10);
+                },
+                _ => match intcode::loader::parse(line) {
+                    Ok(values) => self.queue.extend(values),
+                    Err(e) => eprintln!("{}", e),
+                },
+            }
+        }
+
+        self.queue.pop_front()
+    }
+}
+
+// Prints outputs as they come.
+struct Printer {
+    format: Format,
+    count: usize,
+}
+
+impl Output for Printer {
+    fn write(&mut self, value: i64) {
+        match self.format {
+            Format::Lines => println!("{}", value),
+            Format::Csv if self.count == 0 => print!("{}", value),
+            Format::Csv => print!(",{}", value),
+            Format::Ascii if (0..=127).contains(&value) => print!("{}", value as u8 as char),
+            Format::Ascii => println!("[{}]", value),
+        }
+
+        self.count += 1;
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut inputs = VecDeque::new();
+    let mut interactive = false;
+    let mut sets = Vec::new();
+    let mut format = Format::Lines;
+    let mut steps = None;
+    let mut cells = Vec::new();
+    let mut path = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--input" => {
+                i += 1;
+                match args.get(i).map(|s| intcode::loader::parse(s)) {
+                    Some(Ok(values)) => inputs.extend(values),
+                    _ => usage(),
+                }
+            },
+            "--input-file" => {
+                i += 1;
+                let file = args.get(i).unwrap_or_else(|| usage());
+                inputs.extend(intcode::load(file).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", file, e);
+                    process::exit(1);
+                }));
+            },
+            "--interactive" => interactive = true,
+            "--set" => {
+                i += 1;
+                let set = args.get(i).and_then(|s| {
+                    let mut parts = s.splitn(2, '=');
+                    Some((parts.next()?.parse::<usize>().ok()?, parts.next()?.parse::<i64>().ok()?))
+                });
+                sets.push(set.unwrap_or_else(|| usage()));
+            },
+            "--format" => {
+                i += 1;
+                format = match args.get(i).map(String::as_str) {
+                    Some("lines") => Format::Lines,
+                    Some("csv") => Format::Csv,
+                    Some("ascii") => Format::Ascii,
+                    _ => usage(),
+                };
+            },
+            "--steps" => {
+                i += 1;
+                steps = Some(args.get(i).and_then(|n| n.parse::<u64>().ok()).unwrap_or_else(|| usage()));
+            },
+            "--print" => {
+                i += 1;
+                let addresses: Option<Vec<usize>> =
+                    args.get(i).and_then(|s| s.split(',').map(|a| a.trim().parse::<usize>().ok()).collect());
+                cells.extend(addresses.unwrap_or_else(|| usage()));
+            },
+            arg => path = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let path = path.unwrap_or_else(|| usage());
+    let program = intcode::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut machine = Machine::new(&program);
+
+    for (address, value) in sets {
+        machine.set(address, value).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
+    }
+
+    let mut input = Inputs { queue: inputs, interactive, format };
+    let mut output = Printer { format, count: 0 };
+    let mut executed = 0;
+
+    let result = loop {
+        if steps == Some(executed) {
+            break Ok(State::Running);
+        }
+
+        match machine.step(&mut input, &mut output) {
+            Ok(State::Running) => executed += 1,
+            result => break result,
+        }
+    };
+
+    if format == Format::Csv && output.count > 0 {
+        println!();
+    }
+
+    for address in cells {
+        println!("{}: {}", address, machine.get(address));
+    }
+
+    match result {
+        Ok(State::Halted) => (),
+        Ok(State::Running) => {
+            eprintln!("stopped after {} steps at {}", executed, machine.ip());
+            process::exit(1);
+        },
+        Ok(State::WaitingForInput) => {
+            eprintln!("stopped waiting for input at {}", machine.ip());
+            process::exit(1);
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    }
+}
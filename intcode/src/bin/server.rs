@@ -0,0 +1,78 @@
+// Serves Intcode machines to other programs, see the server module for the protocol.
+//
+// Usage: server SOCKET
+//        server --tcp ADDRESS
+//
+// SOCKET is the path of the Unix socket to listen on, which is replaced if it is a socket already. --tcp listens on
+// an address like 127.0.0.1:4000 instead. Anyone who can connect can run code on the server, so only loopback
+// addresses are allowed.
+
+use std::env;
+use std::fs;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixListener;
+use std::process;
+use std::sync::Arc;
+
+use intcode::server::Server;
+
+const USAGE: &str = "Usage: server SOCKET\n       server --tcp ADDRESS";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+// The addresses to listen on, if they are all loopback addresses.
+fn loopback(address: &str) -> Result<Vec<SocketAddr>, String> {
+    let addresses: Vec<SocketAddr> = address.to_socket_addrs().map_err(|e| format!("{}: {}", address, e))?.collect();
+
+    match addresses.iter().find(|a| !a.ip().is_loopback()) {
+        Some(a) => Err(format!("{}: {} is not a loopback address", address, a.ip())),
+        None => Ok(addresses),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let server = Arc::new(Server::new());
+
+    match args.as_slice() {
+        [flag, address] if flag == "--tcp" => {
+            let addresses = loopback(address).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+            let listener = TcpListener::bind(&addresses[..]).unwrap_or_else(|e| {
+                eprintln!("{}: {}", address, e);
+                process::exit(1);
+            });
+
+            eprintln!("listening on {}", listener.local_addr().map_or(address.to_string(), |a| a.to_string()));
+            server.listen_tcp(listener)
+        },
+        [path] if !path.starts_with("--") => {
+            if let Ok(metadata) = fs::symlink_metadata(path) {
+                if !metadata.file_type().is_socket() {
+                    eprintln!("{}: exists and is not a socket", path);
+                    process::exit(1);
+                }
+
+                fs::remove_file(path).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path, e);
+                    process::exit(1);
+                });
+            }
+
+            let listener = UnixListener::bind(path).unwrap_or_else(|e| {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            });
+
+            eprintln!("listening on {}", path);
+            server.listen_unix(listener)
+        },
+        _ => usage(),
+    }
+}
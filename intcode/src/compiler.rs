@@ -0,0 +1,627 @@
+// A compiler for a tiny language, so programs for the machine don't have to be written by hand:
+//
+//     // prints the factorial of the input
+//     fn factorial(n) {
+//         if n < 2 {
+//             return 1;
+//         }
+//         return n * factorial(n - 1);
+//     }
+//
+//     fn main() {
+//         output(factorial(input()));
+//     }
+//
+// All values are integers. There are variables (`let x = 1;`, `x = x + 1;`), the operators `+ - * < > <= >= ==
+// != && || !` and unary `-`, `if`/`else`, `while`, and functions that can call each other and themselves.
+// `input()` reads a value and `output(x)` writes one. `&&` and `||` only evaluate their right side if the left one
+// doesn't decide the result already, and give 0 or 1. The machine can't divide, so neither can the language.
+// Variables belong to the function they are declared in. A function without `return` returns 0.
+//
+// The output is assembly for the `assembler`. The relative base points to the frame of the running function:
+// the return address at offset 0, then the parameters, the variables and the intermediate results. A call
+// copies the arguments into a new frame right after the caller's, moves the relative base there and jumps.
+// Returning puts the value into a single cell, where the caller picks it up.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::assembler;
+use crate::instructions::InstructionSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+fn error<T>(line: usize, message: String) -> Result<T, CompileError> {
+    Err(CompileError { line, message })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Symbol(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+            Token::End => write!(f, "the end"),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "(", ")", "{", "}", ",", ";", "=", "+", "-", "*", "<", ">", "!",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, CompileError> {
+    let mut tokens = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let mut rest = line.split("//").next().unwrap().trim_start();
+
+        while !rest.is_empty() {
+            let length = if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                let length = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                let number = rest[..length].parse::<i64>()
+                    .or_else(|_| error(line_number, format!("{} is too large", &rest[..length])))?;
+                tokens.push((Token::Number(number), line_number));
+                length
+            } else if rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                let length = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+                tokens.push((Token::Name(rest[..length].to_string()), line_number));
+                length
+            } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+                tokens.push((Token::Symbol(symbol), line_number));
+                symbol.len()
+            } else {
+                return error(line_number, format!("unexpected {:?}", rest.chars().next().unwrap()));
+            };
+
+            rest = rest[length..].trim_start();
+        }
+    }
+
+    tokens.push((Token::End, source.lines().count().max(1)));
+
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Expression {
+    Number(i64),
+    Variable(String, usize),
+    Call(String, Vec<Expression>, usize),
+    Unary(&'static str, Box<Expression>),
+    Binary(&'static str, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug)]
+enum Statement {
+    Let(String, Expression, usize),
+    Assign(String, Expression, usize),
+    If(Expression, Vec<Statement>, Vec<Statement>),
+    While(Expression, Vec<Statement>),
+    Return(Expression),
+    Expression(Expression),
+}
+
+#[derive(Debug)]
+struct Function {
+    name: String,
+    parameters: Vec<String>,
+    body: Vec<Statement>,
+    line: usize,
+}
+
+const KEYWORDS: &[&str] = &["fn", "let", "if", "else", "while", "return"];
+
+// Binary operators from the loosest to the tightest binding.
+const PRECEDENCE: &[&[&str]] = &[&["||"], &["&&"], &["==", "!=", "<", ">", "<=", ">="], &["+", "-"], &["*"]];
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.position].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].0.clone();
+
+        if token != Token::End {
+            self.position += 1;
+        }
+
+        token
+    }
+
+    fn accept(&mut self, symbol: &str) -> bool {
+        let matches = match self.peek() {
+            Token::Symbol(s) => *s == symbol,
+            Token::Name(name) => name == symbol,
+            _ => false,
+        };
+
+        if matches {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), CompileError> {
+        if self.accept(symbol) {
+            Ok(())
+        } else {
+            error(self.line(), format!("expected {} instead of {}", symbol, self.peek()))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, CompileError> {
+        match self.next() {
+            Token::Name(name) if !KEYWORDS.contains(&name.as_str()) => Ok(name),
+            token => error(self.line(), format!("expected a name instead of {}", token)),
+        }
+    }
+
+    fn program(&mut self) -> Result<Vec<Function>, CompileError> {
+        let mut functions = Vec::new();
+
+        while *self.peek() != Token::End {
+            let line = self.line();
+            self.expect("fn")?;
+            let name = self.name()?;
+            let mut parameters = Vec::new();
+
+            self.expect("(")?;
+            while !self.accept(")") {
+                if !parameters.is_empty() {
+                    self.expect(",")?;
+                }
+                parameters.push(self.name()?);
+            }
+
+            let body = self.block()?;
+            functions.push(Function { name, parameters, body, line });
+        }
+
+        Ok(functions)
+    }
+
+    fn block(&mut self) -> Result<Vec<Statement>, CompileError> {
+        let mut statements = Vec::new();
+
+        self.expect("{")?;
+        while !self.accept("}") {
+            statements.push(self.statement()?);
+        }
+
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Statement, CompileError> {
+        let line = self.line();
+
+        if self.accept("let") {
+            let name = self.name()?;
+            self.expect("=")?;
+            let value = self.expression(0)?;
+            self.expect(";")?;
+            Ok(Statement::Let(name, value, line))
+        } else if self.accept("if") {
+            let condition = self.expression(0)?;
+            let then = self.block()?;
+            let otherwise = if !self.accept("else") {
+                Vec::new()
+            } else if *self.peek() == Token::Name("if".to_string()) {
+                vec![self.statement()?]
+            } else {
+                self.block()?
+            };
+            Ok(Statement::If(condition, then, otherwise))
+        } else if self.accept("while") {
+            let condition = self.expression(0)?;
+            Ok(Statement::While(condition, self.block()?))
+        } else if self.accept("return") {
+            let value = self.expression(0)?;
+            self.expect(";")?;
+            Ok(Statement::Return(value))
+        } else if matches!(self.tokens.get(self.position + 1), Some((Token::Symbol("="), _))) {
+            let name = self.name()?;
+            self.expect("=")?;
+            let value = self.expression(0)?;
+            self.expect(";")?;
+            Ok(Statement::Assign(name, value, line))
+        } else {
+            let expression = self.expression(0)?;
+            self.expect(";")?;
+            Ok(Statement::Expression(expression))
+        }
+    }
+
+    fn expression(&mut self, level: usize) -> Result<Expression, CompileError> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+
+        let mut left = self.expression(level + 1)?;
+
+        loop {
+            let operator = match self.peek() {
+                Token::Symbol(symbol) if PRECEDENCE[level].contains(symbol) => *symbol,
+                _ => return Ok(left),
+            };
+
+            self.next();
+            let right = self.expression(level + 1)?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, CompileError> {
+        let line = self.line();
+
+        match self.next() {
+            Token::Symbol("-") => match self.unary()? {
+                Expression::Number(number) => Ok(Expression::Number(-number)),
+                operand => Ok(Expression::Unary("-", Box::new(operand))),
+            },
+            Token::Symbol("!") => Ok(Expression::Unary("!", Box::new(self.unary()?))),
+            Token::Symbol("(") => {
+                let expression = self.expression(0)?;
+                self.expect(")")?;
+                Ok(expression)
+            },
+            Token::Number(number) => Ok(Expression::Number(number)),
+            Token::Name(name) if !KEYWORDS.contains(&name.as_str()) => {
+                if !self.accept("(") {
+                    return Ok(Expression::Variable(name, line));
+                }
+
+                let mut arguments = Vec::new();
+                while !self.accept(")") {
+                    if !arguments.is_empty() {
+                        self.expect(",")?;
+                    }
+                    arguments.push(self.expression(0)?);
+                }
+
+                Ok(Expression::Call(name, arguments, line))
+            },
+            token => error(line, format!("unexpected {}", token)),
+        }
+    }
+}
+
+// Generates the code of one function at a time.
+struct Generator<'a> {
+    arities: &'a HashMap<String, usize>,
+    code: Vec<String>,
+    variables: HashMap<String, usize>,
+    // The first free offset in the frame, and the largest it ever was.
+    free: usize,
+    size: usize,
+    labels: &'a mut usize,
+}
+
+// Where the callee leaves the value it returns.
+const RESULT: &str = "result";
+// Replaced by the size of the frame once the whole function is generated. The callee's frame starts there.
+const FRAME: &str = "{frame}";
+
+// Fills in the frame size, also in offsets into the callee's frame like `{frame+2}`.
+fn fill(line: &str, size: usize) -> String {
+    let mut filled = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find("{frame") {
+        let end = start + rest[start..].find('}').unwrap();
+        let offset = rest[start + 6..end].trim_start_matches('+').parse::<usize>().unwrap_or(0);
+
+        filled.push_str(&rest[..start]);
+        filled.push_str(&(size + offset).to_string());
+        rest = &rest[end + 1..];
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+impl<'a> Generator<'a> {
+    fn emit(&mut self, line: String) {
+        self.code.push(format!("    {}", line));
+    }
+
+    fn label(&mut self) -> String {
+        *self.labels += 1;
+        format!("l{}", self.labels)
+    }
+
+    fn place(&mut self, label: &str) {
+        self.code.push(format!("{}:", label));
+    }
+
+    fn temporary(&mut self) -> String {
+        let offset = self.free;
+        self.free += 1;
+        self.size = self.size.max(self.free);
+        format!("[rb+{}]", offset)
+    }
+
+    fn variable(&self, name: &str, line: usize) -> Result<String, CompileError> {
+        match self.variables.get(name) {
+            Some(offset) => Ok(format!("[rb+{}]", offset)),
+            None => error(line, format!("unknown variable {}", name)),
+        }
+    }
+
+    fn declare(&mut self, name: &str, line: usize) -> Result<String, CompileError> {
+        if self.variables.contains_key(name) {
+            return error(line, format!("{} is declared twice", name));
+        }
+
+        let offset = self.free;
+        self.free += 1;
+        self.size = self.size.max(self.free);
+        self.variables.insert(name.to_string(), offset);
+
+        Ok(format!("[rb+{}]", offset))
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), CompileError> {
+        for statement in statements {
+            self.statement(statement)?;
+        }
+
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        // Intermediate results only live until the end of the statement.
+        let variables = self.free;
+
+        match statement {
+            Statement::Let(name, value, line) => {
+                let value = self.expression(value)?;
+                self.free = variables;
+                let target = self.declare(name, *line)?;
+                self.emit(format!("add {}, 0, {}", value, target));
+            },
+            Statement::Assign(name, value, line) => {
+                let target = self.variable(name, *line)?;
+                let value = self.expression(value)?;
+                self.emit(format!("add {}, 0, {}", value, target));
+            },
+            Statement::If(condition, then, otherwise) => {
+                let (other, end) = (self.label(), self.label());
+                let condition = self.expression(condition)?;
+                self.emit(format!("jz {}, {}", condition, other));
+                self.free = variables;
+                self.statements(then)?;
+                self.emit(format!("jz 0, {}", end));
+                self.place(&other);
+                self.statements(otherwise)?;
+                self.place(&end);
+            },
+            Statement::While(condition, body) => {
+                let (start, end) = (self.label(), self.label());
+                self.place(&start);
+                let condition = self.expression(condition)?;
+                self.emit(format!("jz {}, {}", condition, end));
+                self.free = variables;
+                self.statements(body)?;
+                self.emit(format!("jz 0, {}", start));
+                self.place(&end);
+            },
+            Statement::Return(value) => {
+                let value = self.expression(value)?;
+                self.emit(format!("add {}, 0, [{}]", value, RESULT));
+                self.emit("jz 0, [rb+0]".to_string());
+            },
+            Statement::Expression(expression) => {
+                self.expression(expression)?;
+            },
+        }
+
+        // Variables declared along the way stay, only intermediate results are dropped.
+        self.free = variables.max(self.variables.values().map(|offset| offset + 1).max().unwrap_or(0));
+
+        Ok(())
+    }
+
+    // Generates the code computing an expression, and returns the parameter holding its value.
+    fn expression(&mut self, expression: &Expression) -> Result<String, CompileError> {
+        match expression {
+            Expression::Number(number) => Ok(number.to_string()),
+            Expression::Variable(name, line) => self.variable(name, *line),
+            Expression::Call(name, arguments, line) => self.call(name, arguments, *line),
+            Expression::Unary(operator, operand) => {
+                let operand = self.expression(operand)?;
+                let result = self.temporary();
+
+                match *operator {
+                    "-" => self.emit(format!("mul {}, -1, {}", operand, result)),
+                    _ => self.emit(format!("eq {}, 0, {}", operand, result)),
+                }
+
+                Ok(result)
+            },
+            Expression::Binary(operator @ ("&&" | "||"), left, right) => {
+                let left = self.expression(left)?;
+                let result = self.temporary();
+                let end = self.label();
+
+                self.emit(format!("eq {}, 0, {}", left, result));
+                self.emit(format!("eq {}, 0, {}", result, result));
+
+                // The left side decides if it is false for &&, or true for ||.
+                match *operator {
+                    "&&" => self.emit(format!("jz {}, {}", result, end)),
+                    _ => self.emit(format!("jnz {}, {}", result, end)),
+                }
+
+                let right = self.expression(right)?;
+                self.emit(format!("eq {}, 0, {}", right, result));
+                self.emit(format!("eq {}, 0, {}", result, result));
+                self.place(&end);
+
+                Ok(result)
+            },
+            Expression::Binary(operator, left, right) => {
+                let left = self.expression(left)?;
+                let right = self.expression(right)?;
+                let result = self.temporary();
+
+                match *operator {
+                    "+" => self.emit(format!("add {}, {}, {}", left, right, result)),
+                    "*" => self.emit(format!("mul {}, {}, {}", left, right, result)),
+                    "-" => {
+                        self.emit(format!("mul {}, -1, {}", right, result));
+                        self.emit(format!("add {}, {}, {}", left, result, result));
+                    },
+                    "<" => self.emit(format!("lt {}, {}, {}", left, right, result)),
+                    ">" => self.emit(format!("lt {}, {}, {}", right, left, result)),
+                    "==" => self.emit(format!("eq {}, {}, {}", left, right, result)),
+                    "<=" | ">=" | "!=" => {
+                        match *operator {
+                            "<=" => self.emit(format!("lt {}, {}, {}", right, left, result)),
+                            ">=" => self.emit(format!("lt {}, {}, {}", left, right, result)),
+                            _ => self.emit(format!("eq {}, {}, {}", left, right, result)),
+                        }
+                        self.emit(format!("eq {}, 0, {}", result, result));
+                    },
+                    _ => unreachable!(),
+                }
+
+                Ok(result)
+            },
+        }
+    }
+
+    fn call(&mut self, name: &str, arguments: &[Expression], line: usize) -> Result<String, CompileError> {
+        let arity = match name {
+            "input" => 0,
+            "output" => 1,
+            _ => *self.arities.get(name).map_or_else(|| error(line, format!("unknown function {}", name)), Ok)?,
+        };
+
+        if arguments.len() != arity {
+            return error(line, format!("{} takes {} arguments, not {}", name, arity, arguments.len()));
+        }
+
+        let values = arguments.iter().map(|argument| self.expression(argument)).collect::<Result<Vec<_>, _>>()?;
+        let result = self.temporary();
+
+        match name {
+            "input" => self.emit(format!("in {}", result)),
+            "output" => {
+                self.emit(format!("out {}", values[0]));
+                self.emit(format!("add 0, 0, {}", result));
+            },
+            _ => {
+                let back = self.label();
+
+                for (i, value) in values.iter().enumerate() {
+                    self.emit(format!("add {}, 0, [rb+{{frame+{}}}]", value, i + 1));
+                }
+
+                self.emit(format!("add {}, 0, [rb+{}]", back, FRAME));
+                self.emit(format!("arb {}", FRAME));
+                self.emit(format!("jz 0, f_{}", name));
+                self.place(&back);
+                self.emit(format!("arb -{}", FRAME));
+                self.emit(format!("add [{}], 0, {}", RESULT, result));
+            },
+        }
+
+        Ok(result)
+    }
+}
+
+/// Compiles a program into assembly.
+pub fn compile_to_assembly(source: &str) -> Result<String, CompileError> {
+    let functions = Parser { tokens: tokenize(source)?, position: 0 }.program()?;
+    let mut arities = HashMap::new();
+
+    for function in &functions {
+        if ["input", "output"].contains(&function.name.as_str()) || arities.insert(function.name.clone(), function.parameters.len()).is_some() {
+            return error(function.line, format!("function {} is defined twice", function.name));
+        }
+    }
+
+    match arities.get("main") {
+        Some(0) => (),
+        Some(_) => return error(1, "main can't take arguments".to_string()),
+        None => return error(1, "there is no main function".to_string()),
+    }
+
+    let mut assembly = vec![
+        "    arb stack".to_string(),
+        "    add end, 0, [rb+0]".to_string(),
+        "    jz 0, f_main".to_string(),
+        "end:".to_string(),
+        "    halt".to_string(),
+    ];
+    let mut labels = 0;
+
+    for function in &functions {
+        let mut generator = Generator {
+            arities: &arities,
+            code: Vec::new(),
+            variables: HashMap::new(),
+            free: 1,
+            size: 1,
+            labels: &mut labels,
+        };
+
+        for parameter in &function.parameters {
+            generator.declare(parameter, function.line)?;
+        }
+
+        generator.statements(&function.body)?;
+        generator.emit(format!("add 0, 0, [{}]", RESULT));
+        generator.emit("jz 0, [rb+0]".to_string());
+
+        let size = generator.size;
+        assembly.push(format!("f_{}:", function.name));
+        assembly.extend(generator.code.iter().map(|line| fill(line, size)));
+    }
+
+    assembly.push(format!("{}: data 0", RESULT));
+    assembly.push("stack: data 0".to_string());
+
+    Ok(assembly.join("\n") + "\n")
+}
+
+/// Compiles a program for the standard machine.
+pub fn compile(source: &str) -> Result<Vec<i64>, CompileError> {
+    let assembly = compile_to_assembly(source)?;
+
+    Ok(assembler::assemble(&InstructionSet::standard(), &assembly).expect("The compiler generates valid assembly"))
+}
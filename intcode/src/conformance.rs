@@ -0,0 +1,123 @@
+// The example programs from the puzzle descriptions of days 2, 5, 7 and 9, together with what they are
+// supposed to do. Every `Engine` has to get all of them right.
+
+use crate::engine::Engine;
+use crate::machine::{Error, State};
+
+/// Programs in here are small, anything needing more instructions than this is stuck.
+pub const BUDGET: usize = 100_000;
+
+#[derive(Debug)]
+pub struct Case {
+    pub name: &'static str,
+    pub program: &'static [i64],
+    pub inputs: &'static [i64],
+    pub outputs: &'static [i64],
+    /// The start of the memory once the program halted, if the example says what it looks like.
+    pub memory: Option<&'static [i64]>,
+    pub result: Result<State, Error>,
+}
+
+const fn halts(name: &'static str, program: &'static [i64], inputs: &'static [i64], outputs: &'static [i64]) -> Case {
+    Case { name, program, inputs, outputs, memory: None, result: Ok(State::Halted) }
+}
+
+const fn becomes(name: &'static str, program: &'static [i64], memory: &'static [i64]) -> Case {
+    Case { name, program, inputs: &[], outputs: &[], memory: Some(memory), result: Ok(State::Halted) }
+}
+
+const fn fails(name: &'static str, program: &'static [i64], inputs: &'static [i64], error: Error) -> Case {
+    Case { name, program, inputs, outputs: &[], memory: None, result: Err(error) }
+}
+
+const COMPARE_POSITION_EQUAL: &[i64] = &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+const COMPARE_POSITION_LESS: &[i64] = &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+const COMPARE_IMMEDIATE_EQUAL: &[i64] = &[3, 3, 1108, -1, 8, 3, 4, 3, 99];
+const COMPARE_IMMEDIATE_LESS: &[i64] = &[3, 3, 1107, -1, 8, 3, 4, 3, 99];
+const JUMP_POSITION: &[i64] = &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+const JUMP_IMMEDIATE: &[i64] = &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+const COMPARE_TO_EIGHT: &[i64] = &[
+    3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31,
+    1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104,
+    999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99,
+];
+const AMPLIFIER_SERIES: &[i64] = &[3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0];
+const AMPLIFIER_REVERSED: &[i64] = &[
+    3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23,
+    101, 5, 23, 23, 1, 24, 23, 23, 4, 23, 99, 0, 0,
+];
+const QUINE: &[i64] = &[109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+
+pub const CASES: &[Case] = &[
+    // day 2
+    becomes("day02 walkthrough", &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50], &[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]),
+    becomes("day02 1 + 1", &[1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]),
+    becomes("day02 3 * 2", &[2, 3, 0, 3, 99], &[2, 3, 0, 6, 99]),
+    becomes("day02 99 * 99", &[2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801]),
+    becomes("day02 self-modifying", &[1, 1, 1, 4, 99, 5, 6, 0, 99], &[30, 1, 1, 4, 2, 5, 6, 0, 99]),
+    // day 5
+    halts("day05 echo", &[3, 0, 4, 0, 99], &[7], &[7]),
+    becomes("day05 parameter modes", &[1002, 4, 3, 4, 33], &[1002, 4, 3, 4, 99]),
+    becomes("day05 negative numbers", &[1101, 100, -1, 4, 0], &[1101, 100, -1, 4, 99]),
+    halts("day05 position mode, equal to 8", COMPARE_POSITION_EQUAL, &[8], &[1]),
+    halts("day05 position mode, not equal to 8", COMPARE_POSITION_EQUAL, &[7], &[0]),
+    halts("day05 position mode, less than 8", COMPARE_POSITION_LESS, &[7], &[1]),
+    halts("day05 position mode, not less than 8", COMPARE_POSITION_LESS, &[8], &[0]),
+    halts("day05 immediate mode, equal to 8", COMPARE_IMMEDIATE_EQUAL, &[8], &[1]),
+    halts("day05 immediate mode, not equal to 8", COMPARE_IMMEDIATE_EQUAL, &[9], &[0]),
+    halts("day05 immediate mode, less than 8", COMPARE_IMMEDIATE_LESS, &[7], &[1]),
+    halts("day05 immediate mode, not less than 8", COMPARE_IMMEDIATE_LESS, &[8], &[0]),
+    halts("day05 position mode jump, zero", JUMP_POSITION, &[0], &[0]),
+    halts("day05 position mode jump, non-zero", JUMP_POSITION, &[5], &[1]),
+    halts("day05 immediate mode jump, zero", JUMP_IMMEDIATE, &[0], &[0]),
+    halts("day05 immediate mode jump, non-zero", JUMP_IMMEDIATE, &[5], &[1]),
+    halts("day05 below 8", COMPARE_TO_EIGHT, &[7], &[999]),
+    halts("day05 equal to 8", COMPARE_TO_EIGHT, &[8], &[1000]),
+    halts("day05 above 8", COMPARE_TO_EIGHT, &[9], &[1001]),
+    // day 7, a single amplifier at a time
+    halts("day07 amplifier A", AMPLIFIER_SERIES, &[4, 0], &[4]),
+    halts("day07 amplifier B", AMPLIFIER_SERIES, &[3, 4], &[43]),
+    halts("day07 amplifier E", AMPLIFIER_SERIES, &[0, 4321], &[43210]),
+    halts("day07 reversed amplifier A", AMPLIFIER_REVERSED, &[0, 0], &[5]),
+    halts("day07 reversed amplifier B", AMPLIFIER_REVERSED, &[1, 5], &[54]),
+    // day 9
+    halts("day09 quine", QUINE, &[], QUINE),
+    halts("day09 16-digit number", &[1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[], &[1219070632396864]),
+    halts("day09 large number", &[104, 1125899906842624, 99], &[], &[1125899906842624]),
+    halts("day09 relative base", &[109, 2000, 109, 19, 204, -34, 99], &[], &[0]),
+    // what has to go wrong
+    fails("unknown opcode", &[1, 0, 0, 0, 98], &[], Error::UnknownOpcode { address: 4, opcode: 98 }),
+    fails("unknown mode", &[301, 0, 0, 0, 99], &[], Error::UnknownMode { address: 0, mode: 3 }),
+    fails("write in immediate mode", &[11101, 1, 1, 0, 99], &[], Error::ImmediateWrite { address: 0 }),
+    fails("negative address", &[1, -1, 0, 0, 99], &[], Error::NegativeAddress { address: 0, target: -1 }),
+    fails("overflow", &[1102, 4611686018427387904, 2, 0, 99], &[], Error::Overflow { address: 0 }),
+    fails("end of the program", &[1101, 1, 1, 5], &[], Error::UnknownOpcode { address: 4, opcode: 0 }),
+    Case { name: "missing input", program: &[3, 0, 99], inputs: &[], outputs: &[], memory: None, result: Ok(State::WaitingForInput) },
+];
+
+/// Runs every case on the given engine and describes everything it got wrong.
+pub fn check(engine: &dyn Engine) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    for case in CASES {
+        let execution = engine.execute(case.program, case.inputs, BUDGET);
+
+        if execution.result != case.result {
+            failures.push(format!("{}: {} ended with {:?} instead of {:?}", engine.name(), case.name, execution.result, case.result));
+        }
+
+        if execution.outputs != case.outputs {
+            failures.push(format!("{}: {} output {:?} instead of {:?}", engine.name(), case.name, execution.outputs, case.outputs));
+        }
+
+        if let Some(memory) = case.memory {
+            let start = &execution.memory[..memory.len().min(execution.memory.len())];
+
+            if start != memory {
+                failures.push(format!("{}: {} left {:?} in memory instead of {:?}", engine.name(), case.name, start, memory));
+            }
+        }
+    }
+
+    failures
+}
@@ -0,0 +1,169 @@
+// Keeping track of which instructions a run executed and which way its branches went. Jumps (jnz and jz) are taken
+// or not, comparisons (lt and eq) come out true or false. They are recognized by their definitions, whatever
+// opcode they are registered under. The report shows the disassembly with hit counts, and points out branches
+// that only ever went one way.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::disassembler::{self, Line};
+use crate::instructions::{self, Definition, InstructionSet};
+use crate::io::{Input, Output};
+use crate::machine::{Error, Machine, State};
+
+/// How often a branch went either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Branch {
+    /// Jumps taken, or comparisons that were true.
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+impl Branch {
+    pub fn is_one_sided(&self) -> bool {
+        self.taken == 0 || self.not_taken == 0
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    hits: HashMap<usize, u64>,
+    branches: HashMap<usize, Branch>,
+    instructions: InstructionSet,
+}
+
+// How an instruction branches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// A jump taken if its condition isn't 0.
+    JumpIfTrue,
+    /// A jump taken if its condition is 0.
+    JumpIfFalse,
+    Comparison,
+}
+
+// How the instruction branches, if it is one of the standard branching instructions.
+fn kind(definition: &Definition) -> Option<Kind> {
+    let kind = match definition.mnemonic {
+        "jnz" => Kind::JumpIfTrue,
+        "jz" => Kind::JumpIfFalse,
+        "lt" | "eq" => Kind::Comparison,
+        _ => return None,
+    };
+
+    match instructions::shared().by_mnemonic(definition.mnemonic) {
+        Some(standard) if standard == definition => Some(kind),
+        _ => None,
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    /// How often the instruction at the address was executed.
+    pub fn hits(&self, address: usize) -> u64 {
+        self.hits.get(&address).cloned().unwrap_or(0)
+    }
+
+    /// Every address an instruction was executed at.
+    pub fn executed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits.keys().cloned()
+    }
+
+    pub fn branch(&self, address: usize) -> Option<Branch> {
+        self.branches.get(&address).cloned()
+    }
+
+    /// Same as `Machine::run`, counting along the way.
+    pub fn run<I, O>(&mut self, machine: &mut Machine, input: &mut I, output: &mut O) -> Result<State, Error>
+    where
+        I: Input + ?Sized,
+        O: Output + ?Sized,
+    {
+        self.instructions = machine.instructions().clone();
+
+        let kinds: HashMap<i64, Kind> = self.instructions.definitions()
+            .filter_map(|definition| Some((definition.opcode, kind(definition)?)))
+            .collect();
+
+        loop {
+            let ip = machine.ip();
+            let instruction = machine.get(ip);
+            let kind = kinds.get(&(instruction % 100)).cloned();
+            let condition = machine.parameter(instruction, 1).ok();
+            let target = machine.write_target();
+            let state = machine.step(input, output)?;
+
+            if state == State::WaitingForInput {
+                return Ok(state);
+            }
+
+            *self.hits.entry(ip).or_insert(0) += 1;
+
+            let taken = match (kind, condition, target) {
+                (Some(Kind::JumpIfTrue), Some(condition), _) => Some(condition != 0),
+                (Some(Kind::JumpIfFalse), Some(condition), _) => Some(condition == 0),
+                (Some(Kind::Comparison), _, Some(target)) => Some(machine.get(target) == 1),
+                _ => None,
+            };
+
+            if let Some(taken) = taken {
+                let branch = self.branches.entry(ip).or_default();
+
+                if taken {
+                    branch.taken += 1;
+                } else {
+                    branch.not_taken += 1;
+                }
+            }
+
+            if state == State::Halted {
+                return Ok(state);
+            }
+        }
+    }
+
+    /// The disassembly of the program with how often every instruction ran, followed by a summary.
+    pub fn report(&self, program: &[i64]) -> String {
+        let starts: HashSet<usize> = self.hits.keys().cloned().collect();
+        let mut report = String::new();
+        let mut instructions = 0;
+        let mut executed = 0;
+        let mut one_sided = 0;
+
+        for line in disassembler::disassemble_from(&self.instructions, program, &starts) {
+            let hits = self.hits(line.address());
+
+            match hits {
+                0 => write!(report, "{:>8} ", "-").unwrap(),
+                hits => write!(report, "{:>8} ", hits).unwrap(),
+            }
+            write!(report, "{}", line).unwrap();
+
+            if let Line::Instruction(_) = line {
+                instructions += 1;
+
+                if hits > 0 {
+                    executed += 1;
+                }
+            }
+
+            if let Some(branch) = self.branch(line.address()) {
+                write!(report, "    taken {}, not taken {}", branch.taken, branch.not_taken).unwrap();
+
+                if branch.is_one_sided() {
+                    one_sided += 1;
+                    report.push_str(if branch.taken == 0 { " (never taken)" } else { " (always taken)" });
+                }
+            }
+
+            report.push('\n');
+        }
+
+        writeln!(report, "{} of {} instructions executed, {} branches only went one way", executed, instructions, one_sided).unwrap();
+
+        report
+    }
+}
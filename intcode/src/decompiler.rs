@@ -0,0 +1,492 @@
+// Lifting programs into pseudo-code that is easier to read than the disassembly.
+//
+// The code is found by following every jump from the start, so data is never mistaken for instructions. An
+// unconditional jump right after storing the address behind it is a call, and the jump target a function.
+// Functions that start by moving the relative base forward have a stack frame: the cell at the old relative
+// base holds the return address, the ones after it are arguments (read before they are written) or locals, and
+// cells beyond the frame are arguments for the next call. A jump back is a loop, a jump forward an `if`, and a
+// forward jump at the end of an `if` an `else`. Jumps that don't fit turn into a `goto`.
+//
+// Cells read or written in position mode become variables like `v63`, unless they are part of the code, which
+// shows as `mem[12]`.
+
+use std::collections::{btree_map, BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+use crate::disassembler::{self, Instruction, Parameter};
+use crate::instructions::{InstructionSet, Role};
+
+// The condition under which a conditional jump is taken: its first parameter being zero or not.
+struct Condition {
+    value: Parameter,
+    when_zero: bool,
+}
+
+enum Jump {
+    Never,
+    Always(Option<usize>),
+    If(Condition, Option<usize>),
+}
+
+struct Call {
+    function: usize,
+    jump: usize,
+    back: usize,
+    frame: i64,
+}
+
+struct Program {
+    instructions: InstructionSet,
+    code: BTreeMap<usize, Instruction>,
+    // Every address covered by an instruction.
+    covered: HashSet<usize>,
+    // By the address of the instruction storing the return address.
+    calls: BTreeMap<usize, Call>,
+    functions: BTreeSet<usize>,
+}
+
+// The value an add or multiply writes if both its operands are immediate.
+fn constant(instruction: &Instruction) -> Option<i64> {
+    match (instruction.opcode, &instruction.parameters[..]) {
+        (1, [Parameter::Immediate(a), Parameter::Immediate(b), _]) => a.checked_add(*b),
+        (2, [Parameter::Immediate(a), Parameter::Immediate(b), _]) => a.checked_mul(*b),
+        _ => None,
+    }
+}
+
+fn immediate_arb(instruction: &Instruction) -> Option<i64> {
+    match (instruction.opcode, &instruction.parameters[..]) {
+        (9, [Parameter::Immediate(offset)]) => Some(*offset),
+        _ => None,
+    }
+}
+
+impl Program {
+    fn discover(memory: &[i64], executed: &BTreeSet<usize>) -> Program {
+        let instructions = InstructionSet::standard();
+        let mut program = Program {
+            instructions,
+            code: BTreeMap::new(),
+            covered: HashSet::new(),
+            calls: BTreeMap::new(),
+            functions: BTreeSet::new(),
+        };
+        let mut pending: Vec<usize> = executed.iter().rev().cloned().collect();
+        pending.push(0);
+
+        program.functions.insert(0);
+
+        while let Some(address) = pending.pop() {
+            if address >= memory.len() || program.covered.contains(&address) {
+                continue;
+            }
+
+            let instruction = match disassembler::decode(&program.instructions, memory, address) {
+                Some(instruction) if address + instruction.size() <= memory.len() => instruction,
+                _ => continue,
+            };
+            let next = address + instruction.size();
+
+            match instruction.opcode {
+                99 => (),
+                5 | 6 => match program.jump(&instruction) {
+                    Jump::Never => pending.push(next),
+                    Jump::Always(Some(target)) => {
+                        pending.push(target);
+
+                        if let Some((store, frame)) = program.call_before(address, next) {
+                            program.calls.insert(store, Call { function: target, jump: address, back: next, frame });
+                            program.functions.insert(target);
+                            pending.push(next);
+                        }
+                    },
+                    Jump::Always(None) => (),
+                    Jump::If(_, target) => {
+                        pending.extend(target);
+                        pending.push(next);
+                    },
+                },
+                _ => pending.push(next),
+            }
+
+            program.covered.extend(address..next);
+            program.code.insert(address, instruction);
+        }
+
+        program
+    }
+
+    // The instructions from one address up to another. A jump into the middle of an instruction can make the
+    // range run backwards, which leaves it empty.
+    fn between(&self, from: usize, to: usize) -> btree_map::Range<'_, usize, Instruction> {
+        self.code.range(from..to.max(from))
+    }
+
+    // The instruction that ends right where the given address starts.
+    fn before(&self, address: usize) -> Option<&Instruction> {
+        self.code.range(..address).next_back().map(|(_, i)| i).filter(|i| i.address + i.size() == address)
+    }
+
+    // Whether the jump at the address is preceded by storing the address after it, maybe followed by moving the
+    // relative base. Returns the address of the store and how far the relative base moved.
+    fn call_before(&self, jump: usize, back: usize) -> Option<(usize, i64)> {
+        let mut previous = self.before(jump)?;
+        let mut frame = 0;
+
+        if let Some(offset) = immediate_arb(previous) {
+            frame = offset;
+            previous = self.before(previous.address)?;
+        }
+
+        if constant(previous) == Some(back as i64) {
+            Some((previous.address, frame))
+        } else {
+            None
+        }
+    }
+
+    fn jump(&self, instruction: &Instruction) -> Jump {
+        let target = match instruction.parameters[1] {
+            Parameter::Immediate(target) if target >= 0 => Some(target as usize),
+            _ => None,
+        };
+        let when_zero = instruction.opcode == 6;
+
+        match instruction.parameters[0] {
+            Parameter::Immediate(value) if (value == 0) == when_zero => Jump::Always(target),
+            Parameter::Immediate(_) => Jump::Never,
+            value => Jump::If(Condition { value, when_zero }, target),
+        }
+    }
+
+    // The jump an instruction makes, or `None` if it isn't a jump.
+    fn jump_of(&self, instruction: &Instruction) -> Option<Jump> {
+        match instruction.opcode {
+            5 | 6 => Some(self.jump(instruction)),
+            _ => None,
+        }
+    }
+
+    // Moving the relative base back right before returning.
+    fn is_epilogue(&self, instruction: &Instruction) -> bool {
+        let next = instruction.address + instruction.size();
+
+        immediate_arb(instruction).is_some_and(|n| n < 0)
+            && self.code.get(&next).is_some_and(|i| matches!(self.jump_of(i), Some(Jump::Always(None))))
+    }
+}
+
+// How the cells of a function are called.
+struct Names<'a> {
+    program: &'a Program,
+    frame: Option<i64>,
+    arguments: BTreeSet<i64>,
+}
+
+impl<'a> Names<'a> {
+    fn new(program: &'a Program, start: usize, end: usize) -> Names<'a> {
+        let frame = program.code.get(&start).and_then(immediate_arb).filter(|n| *n > 0);
+        let mut arguments = BTreeSet::new();
+        let mut seen = HashSet::new();
+
+        if let Some(frame) = frame {
+            for instruction in program.between(start, end).map(|(_, i)| i) {
+                let roles = &program.instructions.get(instruction.opcode).unwrap().roles;
+
+                for (parameter, role) in instruction.parameters.iter().zip(roles) {
+                    if let Parameter::Relative(offset) = parameter {
+                        let slot = offset + frame;
+
+                        if (1..frame).contains(&slot) && seen.insert(slot) && *role == Role::Read {
+                            arguments.insert(slot);
+                        }
+                    }
+                }
+            }
+        }
+
+        Names { program, frame, arguments }
+    }
+
+    fn name(&self, parameter: &Parameter) -> String {
+        match *parameter {
+            Parameter::Immediate(value) => value.to_string(),
+            Parameter::Position(address) if address >= 0 && self.program.covered.contains(&(address as usize)) => {
+                format!("mem[{}]", address)
+            },
+            Parameter::Position(address) => format!("v{}", address),
+            Parameter::Relative(offset) => match self.frame {
+                Some(frame) if offset + frame == 0 => "return_address".to_string(),
+                Some(frame) if self.arguments.contains(&(offset + frame)) => format!("arg{}", offset + frame),
+                Some(frame) if (1..frame).contains(&(offset + frame)) => format!("local{}", offset + frame),
+                Some(_) if offset >= 0 => format!("callee[{}]", offset),
+                _ => format!("rb[{}]", offset),
+            },
+        }
+    }
+
+    fn taken(&self, condition: &Condition) -> String {
+        let value = self.name(&condition.value);
+        if condition.when_zero { format!("!{}", value) } else { value }
+    }
+
+    fn not_taken(&self, condition: &Condition) -> String {
+        let value = self.name(&condition.value);
+        if condition.when_zero { value } else { format!("!{}", value) }
+    }
+
+    fn is_return(&self, parameter: &Parameter) -> bool {
+        match (*parameter, self.frame) {
+            (Parameter::Relative(offset), Some(frame)) => offset + frame == 0,
+            _ => false,
+        }
+    }
+
+    fn statement(&self, instruction: &Instruction) -> String {
+        let p: Vec<String> = instruction.parameters.iter().map(|p| self.name(p)).collect();
+
+        match instruction.opcode {
+            1 if p[1] == "0" => format!("{} = {}", p[2], p[0]),
+            1 if p[0] == "0" => format!("{} = {}", p[2], p[1]),
+            1 if p[1].starts_with('-') && p[1][1..].parse::<i64>().is_ok() => format!("{} = {} - {}", p[2], p[0], &p[1][1..]),
+            1 if p[0].starts_with('-') && p[0][1..].parse::<i64>().is_ok() => format!("{} = {} - {}", p[2], p[1], &p[0][1..]),
+            1 => format!("{} = {} + {}", p[2], p[0], p[1]),
+            2 if p[1] == "1" => format!("{} = {}", p[2], p[0]),
+            2 if p[0] == "1" => format!("{} = {}", p[2], p[1]),
+            2 => format!("{} = {} * {}", p[2], p[0], p[1]),
+            3 => format!("{} = input()", p[0]),
+            4 => format!("output({})", p[0]),
+            7 => format!("{} = {} < {}", p[2], p[0], p[1]),
+            8 => format!("{} = {} == {}", p[2], p[0], p[1]),
+            9 if p[0].starts_with('-') => format!("rb -= {}", &p[0][1..]),
+            9 => format!("rb += {}", p[0]),
+            _ => "halt".to_string(),
+        }
+    }
+}
+
+struct Emitter<'a> {
+    program: &'a Program,
+    names: Names<'a>,
+    output: String,
+    labels: &'a BTreeSet<usize>,
+    gotos: BTreeSet<usize>,
+}
+
+// The loop a block is in: where it starts and where it ends.
+type Loop = Option<(usize, usize)>;
+
+impl<'a> Emitter<'a> {
+    fn line(&mut self, depth: usize, text: &str) {
+        writeln!(self.output, "{}{}", "    ".repeat(depth), text).unwrap();
+    }
+
+    fn goto(&mut self, target: usize) -> String {
+        self.gotos.insert(target);
+        format!("goto l{}", target)
+    }
+
+    fn block(&mut self, start: usize, end: usize, depth: usize, current: Loop, entered: Option<usize>) {
+        let program = self.program;
+        let mut address = start;
+
+        while let Some((&at, instruction)) = program.between(address, end).next() {
+            let next = at + instruction.size();
+
+            if self.labels.contains(&at) && entered != Some(at) {
+                self.line(depth.saturating_sub(1), &format!("l{}:", at));
+            }
+
+            // The last jump back to here, from within this block, makes it a loop.
+            let back = program.between(at, end)
+                .rev()
+                .find(|(&a, i)| {
+                    let target = match program.jump_of(i) {
+                        Some(Jump::Always(target)) | Some(Jump::If(_, target)) => target,
+                        _ => None,
+                    };
+                    target == Some(at) && !program.calls.values().any(|c| c.jump == a)
+                });
+
+            if let (Some((_, jump)), true) = (back, entered != Some(at)) {
+                let end_of_loop = jump.address + jump.size();
+
+                self.line(depth, "loop {");
+                self.block(at, end_of_loop, depth + 1, Some((at, end_of_loop)), Some(at));
+                self.line(depth, "}");
+                address = end_of_loop;
+                continue;
+            }
+
+            if let Some(call) = program.calls.get(&at) {
+                self.line(depth, &format!("call f{}", call.function));
+                address = call.back;
+
+                if let Some(back) = program.code.get(&call.back) {
+                    if call.frame != 0 && immediate_arb(back) == Some(-call.frame) {
+                        address = call.back + back.size();
+                    }
+                }
+                continue;
+            }
+
+            address = next;
+            let last = next >= end;
+
+            match program.jump_of(instruction) {
+                None => {
+                    // Setting up and tearing down the frame.
+                    let hidden = self.names.frame.is_some_and(|frame| {
+                        (at == start && immediate_arb(instruction) == Some(frame))
+                            || (immediate_arb(instruction) == Some(-frame) && program.is_epilogue(instruction))
+                    });
+
+                    let statement = self.names.statement(instruction);
+                    let pointless = matches!(statement.split(" = ").collect::<Vec<_>>()[..], [a, b] if a == b);
+
+                    if !hidden && !pointless {
+                        self.line(depth, &statement);
+                    }
+                },
+                Some(Jump::Never) => (),
+                Some(Jump::Always(Some(target))) => {
+                    if current.is_some_and(|(head, _)| head == target) {
+                        if !last {
+                            self.line(depth, "continue");
+                        }
+                    } else if current.is_some_and(|(_, exit)| exit == target) {
+                        self.line(depth, "break");
+                    } else if target < next || program.between(next, target).next().is_some() {
+                        let goto = self.goto(target);
+                        self.line(depth, &goto);
+                    }
+                },
+                Some(Jump::Always(None)) => {
+                    let text = self.indirect(instruction);
+                    self.line(depth, &text);
+                },
+                Some(Jump::If(condition, Some(target))) => {
+                    if current.is_some_and(|(head, _)| head == target) {
+                        if last {
+                            self.line(depth, &format!("if {} {{ break }}", self.names.not_taken(&condition)));
+                        } else {
+                            self.line(depth, &format!("if {} {{ continue }}", self.names.taken(&condition)));
+                        }
+                    } else if current.is_some_and(|(_, exit)| exit == target) {
+                        self.line(depth, &format!("if {} {{ break }}", self.names.taken(&condition)));
+                    } else if target >= next && target <= end {
+                        // A jump forward at the end of the `if` skips the `else`.
+                        let otherwise = program.before(target)
+                            .filter(|i| i.address >= next && !program.calls.values().any(|c| c.jump == i.address))
+                            .and_then(|i| match program.jump_of(i) {
+                                Some(Jump::Always(Some(end_of_else))) if end_of_else > target && end_of_else <= end => {
+                                    Some((i.address, end_of_else))
+                                },
+                                _ => None,
+                            })
+                            .filter(|(_, end_of_else)| current.is_none_or(|(_, exit)| exit != *end_of_else));
+
+                        let empty = |from: usize, to: usize| program.between(from, to).next().is_none();
+
+                        match otherwise {
+                            Some((jump, end_of_else)) if empty(next, jump) => {
+                                self.line(depth, &format!("if {} {{", self.names.taken(&condition)));
+                                self.block(target, end_of_else, depth + 1, current, None);
+                                address = end_of_else;
+                            },
+                            Some((jump, end_of_else)) => {
+                                self.line(depth, &format!("if {} {{", self.names.not_taken(&condition)));
+                                self.block(next, jump, depth + 1, current, None);
+
+                                // Leave out the `else` if nothing in there was worth showing.
+                                let before_else = self.output.len();
+                                self.line(depth, "} else {");
+                                let after_else = self.output.len();
+                                self.block(target, end_of_else, depth + 1, current, None);
+
+                                if self.output.len() == after_else {
+                                    self.output.truncate(before_else);
+                                }
+                                address = end_of_else;
+                            },
+                            None => {
+                                self.line(depth, &format!("if {} {{", self.names.not_taken(&condition)));
+                                self.block(next, target, depth + 1, current, None);
+                                address = target;
+                            },
+                        }
+
+                        self.line(depth, "}");
+                    } else {
+                        let goto = self.goto(target);
+                        self.line(depth, &format!("if {} {{ {} }}", self.names.taken(&condition), goto));
+                    }
+                },
+                Some(Jump::If(condition, None)) => {
+                    let text = self.indirect(instruction);
+                    self.line(depth, &format!("if {} {{ {} }}", self.names.taken(&condition), text));
+                },
+            }
+        }
+    }
+
+    fn indirect(&self, jump: &Instruction) -> String {
+        let destination = &jump.parameters[1];
+        // Right after the frame is torn down, the relative base is back where the caller left it.
+        let torn_down = self.program.before(jump.address).is_some_and(|i| self.program.is_epilogue(i));
+
+        if *destination == Parameter::Relative(0) && (torn_down || self.names.frame.is_none()) || self.names.is_return(destination) {
+            "return".to_string()
+        } else {
+            format!("goto *{}", self.names.name(destination))
+        }
+    }
+}
+
+/// Turns a program into pseudo-code.
+pub fn decompile(memory: &[i64]) -> String {
+    decompile_traced(memory, &BTreeSet::new())
+}
+
+/// Same as `decompile`, for the memory of a machine that already ran. The addresses it executed are known to
+/// be code even if no jump leads there, and instructions the program changed before running them show the way
+/// they ran, like on day 5, where the program adds the input to one of its own instructions.
+pub fn decompile_traced(memory: &[i64], executed: &BTreeSet<usize>) -> String {
+    let program = Program::discover(memory, executed);
+    let entries: Vec<usize> = program.functions.iter().cloned().collect();
+
+    // Gotos are only known once everything was emitted, so it's done twice: once to find the labels, and once
+    // more to place them.
+    let mut labels = BTreeSet::new();
+
+    for pass in 0..2 {
+        let mut output = String::new();
+        let mut gotos = BTreeSet::new();
+
+        for (i, &start) in entries.iter().enumerate() {
+            let end = entries.get(i + 1).cloned().unwrap_or(memory.len());
+            let names = Names::new(&program, start, end);
+            let parameters: Vec<String> = names.arguments.iter().map(|slot| format!("arg{}", slot)).collect();
+            let name = if start == 0 { "main".to_string() } else { format!("f{}", start) };
+            let mut emitter = Emitter { program: &program, names, output: String::new(), labels: &labels, gotos: BTreeSet::new() };
+
+            emitter.line(0, &format!("fn {}({}) {{", name, parameters.join(", ")));
+            emitter.block(start, end, 1, None, None);
+            emitter.line(0, "}");
+
+            if i > 0 {
+                output.push('\n');
+            }
+            output.push_str(&emitter.output);
+            gotos.extend(emitter.gotos);
+        }
+
+        if pass == 1 {
+            return output;
+        }
+
+        labels = gotos;
+    }
+
+    unreachable!()
+}
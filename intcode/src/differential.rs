@@ -0,0 +1,123 @@
+// Running the same programs on every engine and comparing what they do. When an engine disagrees with the
+// first one, the program and its inputs are shrunk for as long as the disagreement stays, so what is left is
+// short enough to step through by hand.
+
+use crate::engine::{Engine, Execution};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub engine: String,
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+    pub expected: Execution,
+    pub actual: Execution,
+}
+
+// Memory only ever grows, but engines may grow it by different amounts, so zeros at the end don't count.
+fn same(a: &Execution, b: &Execution) -> bool {
+    let trim = |memory: &[i64]| memory.len() - memory.iter().rev().take_while(|&&v| v == 0).count();
+
+    a.outputs == b.outputs && a.result == b.result && a.memory[..trim(&a.memory)] == b.memory[..trim(&b.memory)]
+}
+
+/// Runs a program on every engine. Returns how the first engine that disagrees with the first one differs.
+pub fn compare(engines: &[Box<dyn Engine>], program: &[i64], inputs: &[i64], budget: usize) -> Option<Mismatch> {
+    let (reference, others) = engines.split_first()?;
+    let expected = reference.execute(program, inputs, budget);
+
+    others.iter().find_map(|engine| {
+        let actual = engine.execute(program, inputs, budget);
+
+        if same(&expected, &actual) {
+            None
+        } else {
+            Some(Mismatch {
+                engine: engine.name().to_string(),
+                program: program.to_vec(),
+                inputs: inputs.to_vec(),
+                expected: expected.clone(),
+                actual,
+            })
+        }
+    })
+}
+
+/// Shrinks the program and inputs of a mismatch for as long as the same engine keeps disagreeing.
+pub fn minimize(engines: &[Box<dyn Engine>], mismatch: Mismatch, budget: usize) -> Mismatch {
+    let reproduces = |program: &[i64], inputs: &[i64]| {
+        compare(engines, program, inputs, budget).filter(|m| m.engine == mismatch.engine)
+    };
+
+    let mut smallest = mismatch.clone();
+    let mut shrunk = true;
+
+    while shrunk {
+        shrunk = false;
+
+        // Leave out ever smaller chunks of the program.
+        let mut size = smallest.program.len() / 2;
+
+        while size > 0 {
+            let mut start = 0;
+
+            while start + size <= smallest.program.len() {
+                let mut program = smallest.program.clone();
+                program.drain(start..start + size);
+
+                match reproduces(&program, &smallest.inputs) {
+                    Some(m) => {
+                        smallest = m;
+                        shrunk = true;
+                    },
+                    None => start += size,
+                }
+            }
+
+            size /= 2;
+        }
+
+        // Leave out inputs.
+        let mut i = 0;
+
+        while i < smallest.inputs.len() {
+            let mut inputs = smallest.inputs.clone();
+            inputs.remove(i);
+
+            match reproduces(&smallest.program, &inputs) {
+                Some(m) => {
+                    smallest = m;
+                    shrunk = true;
+                },
+                None => i += 1,
+            }
+        }
+
+        // Make the remaining values smaller.
+        for i in 0..smallest.program.len() {
+            for value in &[0, 1, smallest.program[i] / 2] {
+                if value.unsigned_abs() >= smallest.program[i].unsigned_abs() {
+                    continue;
+                }
+
+                let mut program = smallest.program.clone();
+                program[i] = *value;
+
+                if let Some(m) = reproduces(&program, &smallest.inputs) {
+                    smallest = m;
+                    shrunk = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    smallest
+}
+
+/// Runs every program in the corpus on every engine and returns the minimized mismatches.
+pub fn run(engines: &[Box<dyn Engine>], corpus: &[(Vec<i64>, Vec<i64>)], budget: usize) -> Vec<Mismatch> {
+    corpus.iter()
+        .filter_map(|(program, inputs)| compare(engines, program, inputs, budget))
+        .map(|mismatch| minimize(engines, mismatch, budget))
+        .collect()
+}
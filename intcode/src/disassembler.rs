@@ -0,0 +1,140 @@
+// Turning programs back into something readable. Instructions are shown as a mnemonic followed by their
+// parameters: `[12]` is position mode, `12` immediate mode and `[rb+12]` relative mode.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::instructions::{InstructionSet, Role};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parameter {
+    Position(i64),
+    Immediate(i64),
+    Relative(i64),
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Parameter::Position(address) => write!(f, "[{}]", address),
+            Parameter::Immediate(value) => write!(f, "{}", value),
+            Parameter::Relative(offset) if *offset < 0 => write!(f, "[rb{}]", offset),
+            Parameter::Relative(offset) => write!(f, "[rb+{}]", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: usize,
+    pub opcode: i64,
+    pub mnemonic: &'static str,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Instruction {
+    /// How many values the instruction takes up in memory.
+    pub fn size(&self) -> usize {
+        self.parameters.len() + 1
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mnemonic)?;
+
+        for (i, parameter) in self.parameters.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { "" } else { "," }, parameter)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Instruction(Instruction),
+    Data { address: usize, value: i64 },
+}
+
+impl Line {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction(instruction) => instruction.address,
+            Line::Data { address, .. } => *address,
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Instruction(instruction) => write!(f, "{:>6}: {}", instruction.address, instruction),
+            Line::Data { address, value } => write!(f, "{:>6}: data {}", address, value),
+        }
+    }
+}
+
+/// The instruction at the given address, if the value there is a valid instruction in the set.
+pub fn decode(instructions: &InstructionSet, memory: &[i64], address: usize) -> Option<Instruction> {
+    let get = |a: usize| memory.get(a).cloned().unwrap_or(0);
+    let value = get(address);
+
+    if value < 0 {
+        return None;
+    }
+
+    let definition = instructions.get(value % 100)?;
+    let count = definition.roles.len();
+    let mut parameters = Vec::new();
+
+    for (n, role) in (1..=count).zip(&definition.roles) {
+        let raw = get(address + n);
+
+        parameters.push(match value / 10_i64.pow(n as u32 + 1) % 10 {
+            0 => Parameter::Position(raw),
+            1 if *role == Role::Read => Parameter::Immediate(raw),
+            2 => Parameter::Relative(raw),
+            _ => return None,
+        });
+    }
+
+    if value / 10_i64.pow(count as u32 + 2) != 0 {
+        return None;
+    }
+
+    Some(Instruction { address, opcode: value % 100, mnemonic: definition.mnemonic, parameters })
+}
+
+/// Goes through the program from the start, decoding instructions where possible and showing everything else as
+/// data. Instructions that would run past the end of the program are data, too.
+pub fn disassemble(program: &[i64]) -> Vec<Line> {
+    disassemble_from(&InstructionSet::standard(), program, &HashSet::new())
+}
+
+/// Same as `disassemble`, but with the given instructions, and the given addresses are known to start
+/// instructions, for example because they were executed. Nothing decoded before them is allowed to swallow them
+/// as a parameter.
+pub fn disassemble_from(instructions: &InstructionSet, program: &[i64], starts: &HashSet<usize>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut address = 0;
+
+    while address < program.len() {
+        let instruction = decode(instructions, program, address)
+            .filter(|instruction| address + instruction.size() <= program.len())
+            .filter(|instruction| !(address + 1..address + instruction.size()).any(|a| starts.contains(&a)));
+
+        match instruction {
+            Some(instruction) => {
+                address += instruction.size();
+                lines.push(Line::Instruction(instruction));
+            },
+            None => {
+                lines.push(Line::Data { address, value: program[address] });
+                address += 1;
+            },
+        }
+    }
+
+    lines
+}
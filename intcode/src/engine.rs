@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+
+use crate::async_machine::{self, AsyncIntcode};
+use crate::machine::{Error, Machine, State, MEMORY_LIMIT};
+
+/// What running a program to completion, or until it ran out of steps, left behind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Execution {
+    pub outputs: Vec<i64>,
+    pub memory: Vec<i64>,
+    /// `State::Running` if the program was stopped after executing as many instructions as it was allowed to.
+    pub result: Result<State, Error>,
+}
+
+/// A way of executing Intcode programs. Every engine has to behave exactly like the `Interpreter`.
+pub trait Engine {
+    fn name(&self) -> &str;
+
+    /// Runs a program with the given inputs, executing at most `budget` instructions.
+    fn execute(&self, program: &[i64], inputs: &[i64], budget: usize) -> Execution;
+}
+
+/// Runs programs on a `Machine`, one step at a time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Interpreter;
+
+impl Engine for Interpreter {
+    fn name(&self) -> &str {
+        "interpreter"
+    }
+
+    fn execute(&self, program: &[i64], inputs: &[i64], budget: usize) -> Execution {
+        let mut machine = Machine::new(program);
+        let mut inputs: VecDeque<i64> = inputs.iter().cloned().collect();
+        let mut outputs = Vec::new();
+        let mut result = Ok(State::Running);
+
+        for _ in 0..budget {
+            result = machine.step(&mut inputs, &mut outputs);
+
+            match result {
+                Ok(State::Running) => continue,
+                _ => break,
+            }
+        }
+
+        Execution { outputs, memory: machine.memory().to_vec(), result }
+    }
+}
+
+/// Runs programs as an `AsyncIntcode` on the current thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Async;
+
+impl Engine for Async {
+    fn name(&self) -> &str {
+        "async"
+    }
+
+    fn execute(&self, program: &[i64], inputs: &[i64], budget: usize) -> Execution {
+        let mut machine = AsyncIntcode::new(Machine::new(program));
+        let (sender, receiver) = async_machine::channel();
+        let mut outputs = Vec::new();
+
+        for input in inputs {
+            sender.send(*input);
+        }
+        drop(sender);
+
+        let result = async_machine::block_on(machine.run_for(receiver, &mut outputs, budget));
+
+        Execution { outputs, memory: machine.machine().memory().to_vec(), result }
+    }
+}
+
+/// Runs programs on an interpreter of its own, written from the puzzle descriptions without anything from
+/// `Machine` or the instruction set, so comparing it to the `Interpreter` tells mistakes in either apart from
+/// agreement by construction.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standalone;
+
+// A program run by the `Standalone` engine.
+struct Computer {
+    memory: Vec<i64>,
+    ip: usize,
+    relative_base: i64,
+}
+
+impl Computer {
+    fn read(&self, address: usize) -> i64 {
+        self.memory.get(address).cloned().unwrap_or(0)
+    }
+
+    fn write(&mut self, address: usize, value: i64) {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = value;
+    }
+
+    fn address(&self, target: i64) -> Result<usize, Error> {
+        if target < 0 {
+            Err(Error::NegativeAddress { address: self.ip, target })
+        } else if target as u64 >= MEMORY_LIMIT as u64 {
+            Err(Error::OutOfMemory { address: self.ip, target })
+        } else {
+            Ok(target as usize)
+        }
+    }
+
+    // The address parameter n of the current instruction refers to, or `None` in immediate mode.
+    fn pointer(&self, n: usize) -> Result<Option<usize>, Error> {
+        let raw = self.read(self.ip + n);
+
+        match self.read(self.ip) / [100, 1000, 10000][n - 1] % 10 {
+            0 => Ok(Some(self.address(raw)?)),
+            1 => Ok(None),
+            2 => {
+                let target = self.relative_base.checked_add(raw).ok_or(Error::Overflow { address: self.ip })?;
+                Ok(Some(self.address(target)?))
+            },
+            mode => Err(Error::UnknownMode { address: self.ip, mode }),
+        }
+    }
+
+    fn load(&self, n: usize) -> Result<i64, Error> {
+        match self.pointer(n)? {
+            Some(address) => Ok(self.read(address)),
+            None => Ok(self.read(self.ip + n)),
+        }
+    }
+
+    fn target(&self, n: usize) -> Result<usize, Error> {
+        self.pointer(n)?.ok_or(Error::ImmediateWrite { address: self.ip })
+    }
+
+    fn step(&mut self, inputs: &mut VecDeque<i64>, outputs: &mut Vec<i64>) -> Result<State, Error> {
+        let address = self.ip;
+        let overflow = Error::Overflow { address };
+
+        match self.read(address) % 100 {
+            opcode @ 1..=2 => {
+                let (a, b) = (self.load(1)?, self.load(2)?);
+                let value = if opcode == 1 { a.checked_add(b) } else { a.checked_mul(b) }.ok_or(overflow)?;
+                let target = self.target(3)?;
+                self.write(target, value);
+                self.ip += 4;
+            },
+            3 => {
+                let target = self.target(1)?;
+
+                match inputs.pop_front() {
+                    Some(value) => self.write(target, value),
+                    None => return Ok(State::WaitingForInput),
+                }
+
+                self.ip += 2;
+            },
+            4 => {
+                outputs.push(self.load(1)?);
+                self.ip += 2;
+            },
+            opcode @ 5..=6 => {
+                let (condition, destination) = (self.load(1)?, self.load(2)?);
+
+                if (condition != 0) == (opcode == 5) {
+                    self.ip = self.address(destination)?;
+                } else {
+                    self.ip += 3;
+                }
+            },
+            opcode @ 7..=8 => {
+                let (a, b) = (self.load(1)?, self.load(2)?);
+                let value = if opcode == 7 { a < b } else { a == b } as i64;
+                let target = self.target(3)?;
+                self.write(target, value);
+                self.ip += 4;
+            },
+            9 => {
+                self.relative_base = self.relative_base.checked_add(self.load(1)?).ok_or(overflow)?;
+                self.ip += 2;
+            },
+            99 => return Ok(State::Halted),
+            opcode => return Err(Error::UnknownOpcode { address, opcode }),
+        }
+
+        Ok(State::Running)
+    }
+}
+
+impl Engine for Standalone {
+    fn name(&self) -> &str {
+        "standalone"
+    }
+
+    fn execute(&self, program: &[i64], inputs: &[i64], budget: usize) -> Execution {
+        let mut computer = Computer { memory: program.to_vec(), ip: 0, relative_base: 0 };
+        let mut inputs: VecDeque<i64> = inputs.iter().cloned().collect();
+        let mut outputs = Vec::new();
+        let mut result = Ok(State::Running);
+
+        for _ in 0..budget {
+            result = computer.step(&mut inputs, &mut outputs);
+
+            if result != Ok(State::Running) {
+                break;
+            }
+        }
+
+        Execution { outputs, memory: computer.memory, result }
+    }
+}
+
+/// Every engine there is, starting with the `Interpreter` all the others are compared to.
+pub fn engines() -> Vec<Box<dyn Engine>> {
+    vec![Box::new(Interpreter), Box::new(Async), Box::new(Standalone)]
+}
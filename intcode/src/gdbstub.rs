@@ -0,0 +1,427 @@
+// Debugging a machine with GDB, or anything else that speaks the GDB remote serial protocol. The machine is a
+// target with two 64-bit registers, `ip` and `rb` for the relative base, described to the debugger as
+// `target.xml`, so a GDB without an Intcode architecture can attach with
+//
+//     (gdb) set tdesc filename target.xml    # only if it doesn't ask for the description itself
+//     (gdb) target remote localhost:1234
+//
+// GDB addresses memory in bytes, so every cell takes 8 bytes, little-endian, and cell n is at address 8n. The
+// `ip` register is an address like that too, `rb` is the relative base as the machine sees it.
+//
+// Supported are reading and writing registers and memory, software breakpoints, single steps and continuing
+// until a breakpoint, the end of the program or an interrupt from the debugger. Values the program outputs are
+// shown on the debugger's console. A machine stops with SIGTRAP after a step or at a breakpoint, with SIGINT when
+// interrupted, and with SIGILL if the machine fails. When it waits for input that isn't there it stops with
+// SIGTRAP without moving on.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::TryInto;
+use std::fmt::Write as _;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+use crate::machine::{Machine, State, MEMORY_LIMIT};
+
+pub const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.intcode.core">
+    <reg name="ip" bitsize="64" type="code_ptr" regnum="0"/>
+    <reg name="rb" bitsize="64" type="int64" regnum="1"/>
+  </feature>
+</target>
+"#;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+// How many instructions run between looking for an interrupt while continuing.
+const INTERRUPT_CHECK: usize = 1024;
+
+fn hex(bytes: &[u8]) -> String {
+    let mut text = String::new();
+
+    for byte in bytes {
+        write!(text, "{:02x}", byte).unwrap();
+    }
+
+    text
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn number(text: &str) -> Option<u64> {
+    u64::from_str_radix(text, 16).ok()
+}
+
+fn register(bytes: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+// An address and a length, as in "m1000,8".
+fn range(text: &str) -> Option<(u64, u64)> {
+    let mut parts = text.splitn(2, ',');
+    Some((number(parts.next()?)?, number(parts.next()?)?))
+}
+
+// Why the machine stopped.
+enum Stop {
+    Signal(u8),
+    Exited,
+}
+
+/// A machine, with what it reads and where it should stop, waiting for a debugger.
+#[derive(Debug)]
+pub struct Stub {
+    machine: Machine,
+    input: VecDeque<i64>,
+    breakpoints: BTreeSet<usize>,
+}
+
+// The connection to the debugger.
+struct Connection {
+    stream: TcpStream,
+    pending: VecDeque<u8>,
+    acks: bool,
+}
+
+impl Connection {
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut buffer = [0; 1024];
+        let n = self.stream.read(&mut buffer)?;
+        self.pending.extend(&buffer[..n]);
+        Ok(n > 0)
+    }
+
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        if self.pending.is_empty() && !self.fill()? {
+            return Ok(None);
+        }
+
+        Ok(self.pending.pop_front())
+    }
+
+    /// The next packet, skipping acknowledgements and interrupts outside of a run. `None` once the debugger is
+    /// gone.
+    fn packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            match self.byte()? {
+                None => return Ok(None),
+                Some(b'$') => (),
+                Some(_) => continue,
+            }
+
+            let mut data = Vec::new();
+
+            loop {
+                match self.byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                }
+            }
+
+            let checksum = [self.byte()?, self.byte()?];
+            let expected = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            let valid = match checksum {
+                [Some(a), Some(b)] => std::str::from_utf8(&[a, b]).ok().and_then(|c| u8::from_str_radix(c, 16).ok()) == Some(expected),
+                _ => return Ok(None),
+            };
+
+            if self.acks {
+                self.stream.write_all(if valid { b"+" } else { b"-" })?;
+            }
+
+            if valid {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+
+        loop {
+            write!(self.stream, "${}#{:02x}", data, checksum)?;
+            self.stream.flush()?;
+
+            if !self.acks {
+                return Ok(());
+            }
+
+            // Anything but a retransmission request counts as an acknowledgement, so interrupts aren't lost.
+            match self.byte()? {
+                Some(b'-') => continue,
+                Some(b'+') | None => return Ok(()),
+                Some(byte) => {
+                    self.pending.push_front(byte);
+                    return Ok(());
+                },
+            }
+        }
+    }
+
+    /// Whether the debugger sent an interrupt, without waiting for one.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        if self.pending.is_empty() {
+            self.stream.set_nonblocking(true)?;
+            let result = self.fill();
+            self.stream.set_nonblocking(false)?;
+
+            match result {
+                Ok(_) => (),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        match self.pending.iter().position(|byte| *byte == 0x03) {
+            Some(i) => {
+                self.pending.remove(i);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+impl Stub {
+    pub fn new(machine: Machine) -> Stub {
+        Stub { machine, input: VecDeque::new(), breakpoints: BTreeSet::new() }
+    }
+
+    /// Values the program reads, in order.
+    pub fn with_input<I: IntoIterator<Item = i64>>(mut self, input: I) -> Stub {
+        self.input.extend(input);
+        self
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// Talks to a debugger until it detaches, kills the target or hangs up.
+    pub fn serve(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        let mut connection = Connection { stream, pending: VecDeque::new(), acks: true };
+
+        while let Some(packet) = connection.packet()? {
+            let reply = match packet.as_bytes().first() {
+                Some(b'c') => self.resume(&mut connection, false)?,
+                Some(b's') => self.resume(&mut connection, true)?,
+                Some(b'k') => return Ok(()),
+                Some(b'D') => {
+                    connection.send("OK")?;
+                    return Ok(());
+                },
+                _ if packet == "QStartNoAckMode" => {
+                    connection.send("OK")?;
+                    connection.acks = false;
+                    continue;
+                },
+                _ => self.answer(&packet),
+            };
+
+            connection.send(&reply)?;
+        }
+
+        Ok(())
+    }
+
+    // The reply to a packet that doesn't run the machine. Packets that aren't supported get an empty reply.
+    fn answer(&mut self, packet: &str) -> String {
+        let (command, arguments) = match (packet.get(..1), packet.get(1..)) {
+            (Some(command), Some(arguments)) => (command, arguments),
+            _ => return String::new(),
+        };
+
+        let reply = match command {
+            "?" => Some(format!("S{:02x}", SIGTRAP)),
+            "g" => Some(self.registers()),
+            "G" => unhex(arguments).and_then(|bytes| {
+                self.set_register(0, register(bytes.get(0..8)?)?)?;
+                self.set_register(1, register(bytes.get(8..16)?)?)?;
+                Some("OK".to_string())
+            }),
+            "p" => number(arguments).and_then(|n| match n {
+                0 => Some(hex(&((self.machine.ip() * 8) as u64).to_le_bytes())),
+                1 => Some(hex(&self.machine.relative_base().to_le_bytes())),
+                _ => None,
+            }),
+            "P" => {
+                let mut parts = arguments.splitn(2, '=');
+                let n = parts.next().and_then(number);
+                let value = parts.next().and_then(unhex).and_then(|bytes| register(&bytes));
+                n.zip(value).and_then(|(n, value)| self.set_register(n, value)).map(|_| "OK".to_string())
+            },
+            "m" => range(arguments).and_then(|(address, length)| self.read_memory(address, length)),
+            "M" => {
+                let mut parts = arguments.splitn(2, ':');
+                let range = parts.next().and_then(range);
+                let data = parts.next().and_then(unhex);
+                range.zip(data).and_then(|((address, length), data)| {
+                    if data.len() as u64 != length {
+                        return None;
+                    }
+                    self.write_memory(address, &data)
+                })
+            },
+            "Z" | "z" => return self.breakpoint(command == "Z", arguments),
+            "H" => return "OK".to_string(),
+            "q" => return self.query(packet),
+            _ => return String::new(),
+        };
+
+        reply.unwrap_or_else(|| "E01".to_string())
+    }
+
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return "PacketSize=4000;qXfer:features:read+;QStartNoAckMode+;swbreak+".to_string();
+        }
+
+        if let Some(request) = packet.strip_prefix("qXfer:features:read:") {
+            let mut parts = request.splitn(2, ':');
+
+            return match (parts.next(), parts.next().and_then(range)) {
+                (Some("target.xml"), Some((offset, length))) => {
+                    let start = (offset as usize).min(TARGET_XML.len());
+                    let end = start.saturating_add(length as usize).min(TARGET_XML.len());
+                    let prefix = if end == TARGET_XML.len() { "l" } else { "m" };
+                    format!("{}{}", prefix, &TARGET_XML[start..end])
+                },
+                (Some(_), Some(_)) => "E00".to_string(),
+                _ => "E01".to_string(),
+            };
+        }
+
+        match packet {
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn registers(&self) -> String {
+        format!("{}{}", hex(&((self.machine.ip() * 8) as u64).to_le_bytes()), hex(&self.machine.relative_base().to_le_bytes()))
+    }
+
+    fn set_register(&mut self, n: u64, value: u64) -> Option<()> {
+        match n {
+            0 if value.is_multiple_of(8) && value / 8 < MEMORY_LIMIT as u64 => self.machine.set_ip((value / 8) as usize),
+            1 => self.machine.relative_base = value as i64,
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    // The cell and the byte in it for an address, if it's inside the memory a machine may use.
+    fn locate(address: u64) -> Option<(usize, usize)> {
+        let cell = address / 8;
+
+        if cell < MEMORY_LIMIT as u64 {
+            Some((cell as usize, (address % 8) as usize))
+        } else {
+            None
+        }
+    }
+
+    fn read_memory(&self, address: u64, length: u64) -> Option<String> {
+        let mut bytes = Vec::new();
+
+        for address in address..address.checked_add(length.min(0x1000))? {
+            let (cell, byte) = Stub::locate(address)?;
+            bytes.push(self.machine.get(cell).to_le_bytes()[byte]);
+        }
+
+        Some(hex(&bytes))
+    }
+
+    fn write_memory(&mut self, address: u64, data: &[u8]) -> Option<String> {
+        for (i, value) in data.iter().enumerate() {
+            let (cell, byte) = Stub::locate(address.checked_add(i as u64)?)?;
+            let mut bytes = self.machine.get(cell).to_le_bytes();
+            bytes[byte] = *value;
+            self.machine.set(cell, i64::from_le_bytes(bytes)).ok()?;
+        }
+
+        Some("OK".to_string())
+    }
+
+    // "Z0,addr,kind" sets a software breakpoint, "z0,addr,kind" removes it. Other kinds aren't supported.
+    fn breakpoint(&mut self, insert: bool, arguments: &str) -> String {
+        let mut parts = arguments.splitn(3, ',');
+
+        if parts.next() != Some("0") {
+            return String::new();
+        }
+
+        match parts.next().and_then(number).and_then(Stub::locate) {
+            Some((cell, 0)) => {
+                if insert {
+                    self.breakpoints.insert(cell);
+                } else {
+                    self.breakpoints.remove(&cell);
+                }
+                "OK".to_string()
+            },
+            _ => "E01".to_string(),
+        }
+    }
+
+    // Runs a single step or until something stops the machine, and returns the stop reply.
+    fn resume(&mut self, connection: &mut Connection, single: bool) -> io::Result<String> {
+        let mut steps: usize = 0;
+
+        let stop = loop {
+            let mut outputs = Vec::new();
+            let result = self.machine.step(&mut self.input, &mut outputs);
+
+            for value in outputs {
+                connection.send(&format!("O{}", hex(format!("{}\n", value).as_bytes())))?;
+            }
+
+            steps += 1;
+
+            match result {
+                Ok(State::Running) => (),
+                Ok(State::Halted) => break Stop::Exited,
+                Ok(State::WaitingForInput) => {
+                    connection.send(&format!("O{}", hex(b"waiting for input\n")))?;
+                    break Stop::Signal(SIGTRAP);
+                },
+                Err(e) => {
+                    connection.send(&format!("O{}", hex(format!("{}\n", e).as_bytes())))?;
+                    break Stop::Signal(SIGILL);
+                },
+            }
+
+            if single {
+                break Stop::Signal(SIGTRAP);
+            }
+
+            if self.breakpoints.contains(&self.machine.ip()) {
+                break Stop::Signal(SIGTRAP);
+            }
+
+            if steps.is_multiple_of(INTERRUPT_CHECK) && connection.interrupted()? {
+                break Stop::Signal(SIGINT);
+            }
+        };
+
+        Ok(match stop {
+            Stop::Signal(signal) => format!("S{:02x}", signal),
+            Stop::Exited => "W00".to_string(),
+        })
+    }
+}
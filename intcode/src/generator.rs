@@ -0,0 +1,74 @@
+// Turns arbitrary bytes into Intcode programs worth running. Running random values mostly fails on the first
+// instruction, so the bytes are used to pick instructions, parameter modes and parameters that point into the
+// program instead, with a few arbitrary values mixed in to make sure those get tried as well. Once the bytes run
+// out every further choice is 0.
+
+const OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+/// A program together with the inputs to run it with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+}
+
+struct Bytes<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn byte(&mut self) -> u8 {
+        let byte = self.bytes.get(self.position).cloned().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    fn below(&mut self, n: i64) -> i64 {
+        self.byte() as i64 % n
+    }
+
+    fn word(&mut self) -> i64 {
+        let mut word = [0; 8];
+
+        for b in word.iter_mut() {
+            *b = self.byte();
+        }
+
+        i64::from_le_bytes(word)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+}
+
+pub fn generate(bytes: &[u8]) -> Generated {
+    let mut bytes = Bytes { bytes, position: 0 };
+    let length = bytes.below(64) + 1;
+    let inputs = (0..bytes.below(8)).map(|_| bytes.below(41) - 20).collect();
+    let mut program = Vec::new();
+
+    while (program.len() as i64) < length && !bytes.is_empty() {
+        match bytes.below(16) {
+            0 => program.push(bytes.word()),
+            1 => program.push(bytes.below(256) - 128),
+            _ => {
+                let opcode = OPCODES[bytes.below(OPCODES.len() as i64) as usize];
+                let modes = bytes.below(3) * 100 + bytes.below(3) * 1000 + bytes.below(3) * 10000;
+                program.push(modes + opcode);
+
+                for _ in 0..3 {
+                    // Mostly addresses within the program, sometimes just beyond it or negative.
+                    program.push(bytes.below(length + 8) - 4);
+                }
+            },
+        }
+    }
+
+    if program.is_empty() {
+        program.push(99);
+    }
+
+    Generated { program, inputs }
+}
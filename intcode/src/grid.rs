@@ -0,0 +1,243 @@
+// Programs that draw output `x, y, tile` triples, one after another. The tiles end up on a canvas that only
+// stores what has been drawn, and can be rendered with a palette mapping tiles to characters. Like the image on
+// day 8, by default tile 0 is blank and everything else is a '*'.
+//
+// `Grid::run` runs a machine drawing on the grid and sends a frame every time the program reads input and when it
+// stops, which for a game is every time it waits for the joystick:
+//
+//     let mut grid = Grid::new(Palette::default()).with_frames(Frames::Terminal);
+//     grid.run(&mut machine, &mut joystick)?;
+//
+// Canvases wider or higher than `MAX_EXTENT` aren't rendered, a stray tile far away would take forever to draw.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::io::{Counted, Input, Output};
+use crate::machine::{Error, Machine, State};
+
+/// The most columns or rows a canvas can be rendered with.
+pub const MAX_EXTENT: u64 = 4096;
+
+/// A canvas with tiles too far apart to render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge {
+    pub width: u128,
+    pub height: u128,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a canvas of {} by {} is too large to render, at most {} are allowed either way",
+            self.width, self.height, MAX_EXTENT)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+#[derive(Debug)]
+pub enum RunError {
+    Machine(Error),
+    /// A frame couldn't be rendered or sent where frames go.
+    Frame(io::Error),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Machine(e) => write!(f, "{}", e),
+            RunError::Frame(e) => write!(f, "frame failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+impl From<Error> for RunError {
+    fn from(e: Error) -> RunError {
+        RunError::Machine(e)
+    }
+}
+
+impl From<io::Error> for RunError {
+    fn from(e: io::Error) -> RunError {
+        RunError::Frame(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Palette {
+    tiles: HashMap<i64, char>,
+    default: char,
+}
+
+impl Palette {
+    /// A palette drawing every tile as `default`.
+    pub fn new(default: char) -> Palette {
+        Palette { tiles: HashMap::new(), default }
+    }
+
+    pub fn with(mut self, tile: i64, c: char) -> Palette {
+        self.tiles.insert(tile, c);
+        self
+    }
+
+    pub fn draw(&self, tile: i64) -> char {
+        self.tiles.get(&tile).cloned().unwrap_or(self.default)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::new('*').with(0, ' ')
+    }
+}
+
+/// Where frames go when `Grid::frame` is called.
+#[derive(Debug, Clone)]
+pub enum Frames {
+    /// Nowhere, rendering is up to the caller.
+    None,
+    /// Clears the terminal and draws the frame.
+    Terminal,
+    /// Writes every frame to its own numbered file in the directory.
+    Files(PathBuf),
+}
+
+#[derive(Debug)]
+pub struct Grid {
+    tiles: HashMap<(i64, i64), i64>,
+    pending: Vec<i64>,
+    palette: Palette,
+    score_at: Option<(i64, i64)>,
+    score: Option<i64>,
+    frames: Frames,
+    frame_count: usize,
+}
+
+impl Grid {
+    pub fn new(palette: Palette) -> Grid {
+        Grid {
+            tiles: HashMap::new(),
+            pending: Vec::new(),
+            palette,
+            score_at: None,
+            score: None,
+            frames: Frames::None,
+            frame_count: 0,
+        }
+    }
+
+    /// Treats the tile drawn at this position as a score instead, like the arcade cabinet showing its score at
+    /// -1, 0.
+    pub fn with_score_at(mut self, x: i64, y: i64) -> Grid {
+        self.score_at = Some((x, y));
+        self
+    }
+
+    pub fn with_frames(mut self, frames: Frames) -> Grid {
+        self.frames = frames;
+        self
+    }
+
+    pub fn tile(&self, x: i64, y: i64) -> Option<i64> {
+        self.tiles.get(&(x, y)).cloned()
+    }
+
+    pub fn tiles(&self) -> &HashMap<(i64, i64), i64> {
+        &self.tiles
+    }
+
+    pub fn score(&self) -> Option<i64> {
+        self.score
+    }
+
+    /// Draws everything between the smallest and largest coordinates drawn to so far. Positions nothing was drawn
+    /// to are blank. The score, if there is one, goes below.
+    pub fn render(&self) -> Result<String, TooLarge> {
+        let mut rendered = String::new();
+
+        if !self.tiles.is_empty() {
+            let xs = self.tiles.keys().map(|(x, _)| *x);
+            let ys = self.tiles.keys().map(|(_, y)| *y);
+            let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+            let (min_y, max_y) = (ys.clone().min().unwrap(), ys.max().unwrap());
+            // Tiles at both ends of the i64 range are 2^64 apart, which doesn't even fit a u64.
+            let width = max_x as i128 - min_x as i128 + 1;
+            let height = max_y as i128 - min_y as i128 + 1;
+
+            if width > MAX_EXTENT as i128 || height > MAX_EXTENT as i128 {
+                return Err(TooLarge { width: width as u128, height: height as u128 });
+            }
+
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    rendered.push(self.tile(x, y).map_or(' ', |tile| self.palette.draw(tile)));
+                }
+                rendered.push('\n');
+            }
+        }
+
+        if let Some(score) = self.score {
+            rendered.push_str(&format!("Score: {}\n", score));
+        }
+
+        Ok(rendered)
+    }
+
+    /// Sends the current state of the canvas wherever frames go.
+    pub fn frame(&mut self) -> io::Result<()> {
+        self.frame_count += 1;
+
+        let render = || self.render().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+
+        match &self.frames {
+            Frames::None => Ok(()),
+            Frames::Terminal => {
+                let mut stdout = io::stdout();
+                write!(stdout, "\x1b[2J\x1b[H{}", render()?)?;
+                stdout.flush()
+            },
+            Frames::Files(directory) => {
+                fs::create_dir_all(directory)?;
+                fs::write(directory.join(format!("frame-{:05}.txt", self.frame_count)), render()?)
+            },
+        }
+    }
+
+    /// Same as `Machine::run` with the grid as output, sending a frame after every instruction that read input
+    /// and once the machine stops.
+    pub fn run<I: Input + ?Sized>(&mut self, machine: &mut Machine, input: &mut I) -> Result<State, RunError> {
+        loop {
+            let mut counted = Counted { input: &mut *input, read: 0 };
+            let state = machine.step(&mut counted, self)?;
+
+            if counted.read > 0 || state != State::Running {
+                self.frame()?;
+            }
+
+            if state != State::Running {
+                return Ok(state);
+            }
+        }
+    }
+}
+
+impl Output for Grid {
+    fn write(&mut self, value: i64) {
+        self.pending.push(value);
+
+        if let [x, y, tile] = self.pending[..] {
+            self.pending.clear();
+
+            if self.score_at == Some((x, y)) {
+                self.score = Some(tile);
+            } else {
+                self.tiles.insert((x, y), tile);
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Comparing two snapshots of a machine's memory, like before and after running day 2 with a noun and a verb.
+// Changed cells are grouped into regions of neighbouring addresses. When the run in between was traced, every
+// changed cell also knows the address of the instruction that wrote it last.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::io::{Input, Output};
+use crate::machine::{Error, Machine, State};
+
+/// The address of the instruction that wrote each cell last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Writers {
+    writers: HashMap<usize, usize>,
+}
+
+impl Writers {
+    pub fn new() -> Writers {
+        Writers::default()
+    }
+
+    pub fn get(&self, address: usize) -> Option<usize> {
+        self.writers.get(&address).cloned()
+    }
+
+    /// Same as `Machine::run`, remembering who writes where along the way.
+    pub fn run<I, O>(&mut self, machine: &mut Machine, input: &mut I, output: &mut O) -> Result<State, Error>
+    where
+        I: Input + ?Sized,
+        O: Output + ?Sized,
+    {
+        loop {
+            let ip = machine.ip();
+            let target = machine.write_target();
+
+            match machine.step(input, output)? {
+                State::Running => {
+                    if let Some(target) = target {
+                        self.writers.insert(target, ip);
+                    }
+                },
+                state => return Ok(state),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub old: Vec<i64>,
+    pub new: Vec<i64>,
+    /// For every cell, the address of the instruction that wrote it last, if known.
+    pub writers: Vec<Option<usize>>,
+}
+
+impl Region {
+    pub fn end(&self) -> usize {
+        self.start + self.new.len()
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}..{}:", self.start, self.end())?;
+
+        for (i, (old, new)) in self.old.iter().zip(&self.new).enumerate() {
+            write!(f, "  {:>6}: {} -> {}", self.start + i, old, new)?;
+
+            if let Some(writer) = self.writers[i] {
+                write!(f, " (written at {})", writer)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The regions that differ between two snapshots. Cells beyond the end of a snapshot count as 0, as they would
+/// read in the machine.
+pub fn diff(before: &[i64], after: &[i64], writers: Option<&Writers>) -> Vec<Region> {
+    let cell = |memory: &[i64], address: usize| memory.get(address).cloned().unwrap_or(0);
+    let mut regions: Vec<Region> = Vec::new();
+
+    for address in 0..before.len().max(after.len()) {
+        let (old, new) = (cell(before, address), cell(after, address));
+
+        if old == new {
+            continue;
+        }
+
+        let writer = writers.and_then(|w| w.get(address));
+
+        match regions.last_mut() {
+            Some(region) if region.end() == address => {
+                region.old.push(old);
+                region.new.push(new);
+                region.writers.push(writer);
+            },
+            _ => regions.push(Region { start: address, old: vec![old], new: vec![new], writers: vec![writer] }),
+        }
+    }
+
+    regions
+}
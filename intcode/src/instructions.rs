@@ -0,0 +1,290 @@
+// The instructions a machine understands. Every opcode has a mnemonic, a role for each of its parameters and a
+// function executing it. The opcodes from the puzzles are registered the same way as any extra ones, so the
+// machine, the disassembler and the assembler don't need to know about either:
+//
+//     let mut instructions = InstructionSet::standard();
+//     instructions.register(10, "div", &[Role::Read, Role::Read, Role::Write], |op| {
+//         let divisor = op.parameter(2)?;
+//         let quotient = op.parameter(1)?.checked_div(divisor).ok_or(Error::Overflow { address: op.address() })?;
+//         op.write(3, quotient)?;
+//         Ok(Effect::Next)
+//     })?;
+//     let machine = Machine::new(&program).with_instructions(instructions);
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use crate::io::{Input, Output};
+use crate::machine::{Error, Machine};
+
+/// What a parameter is used for. Parameters that are written to can't be in immediate mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Read,
+    Write,
+}
+
+/// What happens after an instruction was executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Continue with the instruction after this one.
+    Next,
+    Jump(i64),
+    /// Stay at this instruction and try it again once there is input.
+    Wait,
+    Halt,
+}
+
+/// The instruction being executed, with access to the machine running it.
+pub struct Operation<'a> {
+    pub(crate) machine: &'a mut Machine,
+    pub(crate) instruction: i64,
+    pub(crate) input: &'a mut dyn Input,
+    pub(crate) output: &'a mut dyn Output,
+}
+
+impl<'a> Operation<'a> {
+    /// The address of the instruction.
+    pub fn address(&self) -> usize {
+        self.machine.ip()
+    }
+
+    /// The value of the nth parameter, counting from 1.
+    pub fn parameter(&self, n: u32) -> Result<i64, Error> {
+        self.machine.parameter(self.instruction, n)
+    }
+
+    /// The address the nth parameter points to, counting from 1.
+    pub fn target(&self, n: u32) -> Result<usize, Error> {
+        self.machine.target(self.instruction, n)
+    }
+
+    pub fn write(&mut self, n: u32, value: i64) -> Result<(), Error> {
+        let target = self.target(n)?;
+        self.set(target, value)
+    }
+
+    pub fn set(&mut self, address: usize, value: i64) -> Result<(), Error> {
+        let ip = self.address();
+        let target = i64::try_from(address).unwrap_or(i64::MAX);
+        self.machine.set(address, value).map_err(|_| Error::OutOfMemory { address: ip, target })
+    }
+
+    pub fn read_input(&mut self) -> Option<i64> {
+        self.input.read()
+    }
+
+    pub fn write_output(&mut self, value: i64) {
+        self.output.write(value);
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.machine.relative_base()
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: i64) {
+        self.machine.relative_base = relative_base;
+    }
+}
+
+type Execute = dyn Fn(&mut Operation) -> Result<Effect, Error> + Send + Sync;
+
+#[derive(Clone)]
+pub struct Definition {
+    pub opcode: i64,
+    pub mnemonic: &'static str,
+    pub roles: Vec<Role>,
+    execute: Arc<Execute>,
+}
+
+impl Definition {
+    pub fn execute(&self, operation: &mut Operation) -> Result<Effect, Error> {
+        (self.execute)(operation)
+    }
+}
+
+impl fmt::Debug for Definition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Definition")
+            .field("opcode", &self.opcode)
+            .field("mnemonic", &self.mnemonic)
+            .field("roles", &self.roles)
+            .finish()
+    }
+}
+
+impl PartialEq for Definition {
+    fn eq(&self, other: &Definition) -> bool {
+        self.opcode == other.opcode
+            && self.mnemonic == other.mnemonic
+            && self.roles == other.roles
+            && Arc::as_ptr(&self.execute).cast::<()>() == Arc::as_ptr(&other.execute).cast::<()>()
+    }
+}
+
+impl Eq for Definition {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// Opcodes are the last two digits of an instruction, so they go from 1 to 99.
+    OutOfRange { opcode: i64 },
+    Taken { opcode: i64 },
+    DuplicateMnemonic { mnemonic: &'static str },
+    /// An instruction has to fit its modes into the digits above the opcode.
+    TooManyParameters { opcode: i64 },
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegisterError::OutOfRange { opcode } => write!(f, "opcode {} is not between 1 and 99", opcode),
+            RegisterError::Taken { opcode } => write!(f, "opcode {} is already registered", opcode),
+            RegisterError::DuplicateMnemonic { mnemonic } => write!(f, "mnemonic {} is already registered", mnemonic),
+            RegisterError::TooManyParameters { opcode } => write!(f, "opcode {} has too many parameters", opcode),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+/// More parameters than this and the modes of an instruction don't fit into an `i64` anymore.
+pub const MAX_PARAMETERS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionSet {
+    definitions: Vec<Option<Definition>>,
+}
+
+impl Default for InstructionSet {
+    fn default() -> InstructionSet {
+        InstructionSet::standard()
+    }
+}
+
+impl InstructionSet {
+    /// No instructions at all.
+    pub fn empty() -> InstructionSet {
+        InstructionSet { definitions: vec![None; 100] }
+    }
+
+    /// The instructions from the puzzles.
+    pub fn standard() -> InstructionSet {
+        shared().as_ref().clone()
+    }
+
+    pub fn register<F>(&mut self, opcode: i64, mnemonic: &'static str, roles: &[Role], execute: F) -> Result<(), RegisterError>
+    where
+        F: Fn(&mut Operation) -> Result<Effect, Error> + Send + Sync + 'static,
+    {
+        if !(1..=99).contains(&opcode) {
+            return Err(RegisterError::OutOfRange { opcode });
+        }
+
+        if self.get(opcode).is_some() {
+            return Err(RegisterError::Taken { opcode });
+        }
+
+        if self.by_mnemonic(mnemonic).is_some() {
+            return Err(RegisterError::DuplicateMnemonic { mnemonic });
+        }
+
+        if roles.len() > MAX_PARAMETERS {
+            return Err(RegisterError::TooManyParameters { opcode });
+        }
+
+        self.definitions[opcode as usize] = Some(Definition { opcode, mnemonic, roles: roles.to_vec(), execute: Arc::new(execute) });
+
+        Ok(())
+    }
+
+    pub fn get(&self, opcode: i64) -> Option<&Definition> {
+        if opcode < 0 {
+            return None;
+        }
+
+        self.definitions.get(opcode as usize)?.as_ref()
+    }
+
+    pub fn by_mnemonic(&self, mnemonic: &str) -> Option<&Definition> {
+        self.definitions().find(|definition| definition.mnemonic == mnemonic)
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = &Definition> {
+        self.definitions.iter().flatten()
+    }
+}
+
+/// The standard instructions, built once and shared between all machines that don't bring their own.
+pub(crate) fn shared() -> &'static Arc<InstructionSet> {
+    static STANDARD: OnceLock<Arc<InstructionSet>> = OnceLock::new();
+
+    STANDARD.get_or_init(|| {
+        let mut set = InstructionSet::empty();
+        let arithmetic = [Role::Read, Role::Read, Role::Write];
+
+        type Builtin = fn(&mut Operation) -> Result<Effect, Error>;
+
+        let builtins: [(i64, &'static str, &[Role], Builtin); 10] = [
+            (1, "add", &arithmetic, |op| {
+                let value = op.parameter(1)?.checked_add(op.parameter(2)?).ok_or(Error::Overflow { address: op.address() })?;
+                op.write(3, value)?;
+                Ok(Effect::Next)
+            }),
+            (2, "mul", &arithmetic, |op| {
+                let value = op.parameter(1)?.checked_mul(op.parameter(2)?).ok_or(Error::Overflow { address: op.address() })?;
+                op.write(3, value)?;
+                Ok(Effect::Next)
+            }),
+            (3, "in", &[Role::Write], |op| {
+                let target = op.target(1)?;
+
+                match op.read_input() {
+                    Some(value) => {
+                        op.set(target, value)?;
+                        Ok(Effect::Next)
+                    },
+                    None => Ok(Effect::Wait),
+                }
+            }),
+            (4, "out", &[Role::Read], |op| {
+                let value = op.parameter(1)?;
+                op.write_output(value);
+                Ok(Effect::Next)
+            }),
+            (5, "jnz", &[Role::Read, Role::Read], |op| {
+                let condition = op.parameter(1)?;
+                let destination = op.parameter(2)?;
+                Ok(if condition != 0 { Effect::Jump(destination) } else { Effect::Next })
+            }),
+            (6, "jz", &[Role::Read, Role::Read], |op| {
+                let condition = op.parameter(1)?;
+                let destination = op.parameter(2)?;
+                Ok(if condition == 0 { Effect::Jump(destination) } else { Effect::Next })
+            }),
+            (7, "lt", &arithmetic, |op| {
+                let value = (op.parameter(1)? < op.parameter(2)?) as i64;
+                op.write(3, value)?;
+                Ok(Effect::Next)
+            }),
+            (8, "eq", &arithmetic, |op| {
+                let value = (op.parameter(1)? == op.parameter(2)?) as i64;
+                op.write(3, value)?;
+                Ok(Effect::Next)
+            }),
+            (9, "arb", &[Role::Read], |op| {
+                let offset = op.parameter(1)?;
+                let relative_base = op.relative_base().checked_add(offset).ok_or(Error::Overflow { address: op.address() })?;
+                op.set_relative_base(relative_base);
+                Ok(Effect::Next)
+            }),
+            (99, "halt", &[], |_| Ok(Effect::Halt)),
+        ];
+
+        for (opcode, mnemonic, roles, execute) in builtins.iter() {
+            set.register(*opcode, mnemonic, roles, *execute).expect("The standard instructions are distinct");
+        }
+
+        Arc::new(set)
+    })
+}
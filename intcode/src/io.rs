@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Where a machine gets its values from when it runs an input instruction.
+pub trait Input {
+    /// Returns the next input value, or `None` if there is none yet. The machine then waits at the input
+    /// instruction until it is run again.
+    fn read(&mut self) -> Option<i64>;
+}
+
+/// Where a machine sends the values of its output instructions.
+pub trait Output {
+    fn write(&mut self, value: i64);
+}
+
+impl Input for VecDeque<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+/// A single value that is handed out once.
+impl Input for Option<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.take()
+    }
+}
+
+impl Input for std::vec::IntoIter<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.next()
+    }
+}
+
+impl Output for Vec<i64> {
+    fn write(&mut self, value: i64) {
+        self.push(value);
+    }
+}
+
+impl Output for VecDeque<i64> {
+    fn write(&mut self, value: i64) {
+        self.push_back(value);
+    }
+}
+
+/// Blocks until a value arrives. Once every sender is gone the machine is left waiting for input.
+impl Input for Receiver<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+/// Values sent after the receiving side hung up are dropped.
+impl Output for Sender<i64> {
+    fn write(&mut self, value: i64) {
+        let _ = self.send(value);
+    }
+}
+
+impl<T: Input + ?Sized> Input for &mut T {
+    fn read(&mut self) -> Option<i64> {
+        (**self).read()
+    }
+}
+
+impl<T: Output + ?Sized> Output for &mut T {
+    fn write(&mut self, value: i64) {
+        (**self).write(value)
+    }
+}
+
+// Counts the values taken from an input.
+pub(crate) struct Counted<'a, I: ?Sized> {
+    pub(crate) input: &'a mut I,
+    pub(crate) read: usize,
+}
+
+impl<'a, I: Input + ?Sized> Input for Counted<'a, I> {
+    fn read(&mut self) -> Option<i64> {
+        let value = self.input.read();
+        self.read += value.is_some() as usize;
+        value
+    }
+}
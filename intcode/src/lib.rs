@@ -0,0 +1,35 @@
+// The Intcode computer, shared between all the days that need one.
+//
+// Day 2 introduced the computer with opcodes 1, 2 and 99, day 5 added input/output, jumps, comparisons and
+// parameter modes, and day 9 added relative mode and the relative base offset instruction. The `Machine` in
+// here supports all of them.
+
+pub mod ascii;
+pub mod assembler;
+pub mod async_machine;
+pub mod compiler;
+pub mod conformance;
+pub mod coverage;
+pub mod decompiler;
+pub mod differential;
+pub mod disassembler;
+mod engine;
+pub mod gdbstub;
+pub mod generator;
+pub mod grid;
+pub mod heap;
+pub mod instructions;
+mod io;
+pub mod loader;
+mod machine;
+mod network;
+pub mod server;
+pub mod session;
+pub mod taint;
+
+pub use async_machine::{AsyncInput, AsyncIntcode, AsyncOutput};
+pub use engine::{engines, Async, Engine, Execution, Interpreter, Standalone};
+pub use io::{Input, Output};
+pub use loader::{load, LoadError};
+pub use machine::{BeyondLimit, Error, Machine, Profile, State, MEMORY_LIMIT};
+pub use network::{Network, Outcome, Packet, Route, Transmission, UnknownAddress};
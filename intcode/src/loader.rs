@@ -0,0 +1,168 @@
+// Reading programs from files. Three formats are understood, and told apart by their first bytes:
+//
+// - text, the way the puzzle inputs come: values separated by commas, with any whitespace and line breaks
+//   between them. Whitespace alone separates values as well, so "1 2 3" is the same as "1,2,3", and so are
+//   values on lines of their own like in the examples on day 2,
+// - binary, which starts with `BINARY_MAGIC` followed by every value as a zigzag-encoded LEB128 varint,
+// - gzip, containing either of the other two.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+pub const BINARY_MAGIC: &[u8] = b"INTC";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// A value in a text program that isn't a number. An empty `token` means two commas with nothing in between.
+    Malformed { offset: usize, line: usize, column: usize, token: String },
+    /// A binary program that ends in the middle of a value, or has a value too large to fit.
+    Truncated { offset: usize },
+    InvalidUtf8 { offset: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Malformed { offset, line, column, token } if token.is_empty() => {
+                write!(f, "missing value at offset {} (line {}, column {})", offset, line, column)
+            },
+            LoadError::Malformed { offset, line, column, token } => {
+                write!(f, "{:?} is not a number at offset {} (line {}, column {})", token, offset, line, column)
+            },
+            LoadError::Truncated { offset } => write!(f, "incomplete value at offset {}", offset),
+            LoadError::InvalidUtf8 { offset } => write!(f, "invalid UTF-8 at offset {}", offset),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<i64>, LoadError> {
+    decode(&fs::read(path)?)
+}
+
+/// Reads a program in any of the formats.
+pub fn decode(bytes: &[u8]) -> Result<Vec<i64>, LoadError> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        decode(&decompressed)
+    } else if bytes.starts_with(BINARY_MAGIC) {
+        decode_binary(bytes)
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => parse(text),
+            Err(e) => Err(LoadError::InvalidUtf8 { offset: e.valid_up_to() }),
+        }
+    }
+}
+
+/// Reads a program in the text format. Values are separated by commas or whitespace, or both. A comma after the
+/// last value is fine, but not two commas in a row.
+pub fn parse(text: &str) -> Result<Vec<i64>, LoadError> {
+    let malformed = |offset: usize, token: &str| {
+        let before = &text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+
+        LoadError::Malformed { offset, line, column, token: token.to_string() }
+    };
+
+    let mut program = Vec::new();
+    let mut after_comma = true;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        if c == ',' {
+            if after_comma {
+                return Err(malformed(offset, ""));
+            }
+
+            after_comma = true;
+            continue;
+        }
+
+        let mut end = text.len();
+
+        while let Some(&(o, c)) = chars.peek() {
+            if c == ',' || c.is_whitespace() {
+                end = o;
+                break;
+            }
+
+            chars.next();
+        }
+
+        let token = &text[offset..end];
+        program.push(token.parse::<i64>().map_err(|_| malformed(offset, token))?);
+        after_comma = false;
+    }
+
+    Ok(program)
+}
+
+fn decode_binary(bytes: &[u8]) -> Result<Vec<i64>, LoadError> {
+    let mut program = Vec::new();
+    let mut offset = BINARY_MAGIC.len();
+
+    while offset < bytes.len() {
+        let start = offset;
+        let mut value: u64 = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = *bytes.get(offset).ok_or(LoadError::Truncated { offset: start })?;
+            offset += 1;
+
+            if shift > 63 || (shift == 63 && byte & 0x7e != 0) {
+                return Err(LoadError::Truncated { offset: start });
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        program.push((value >> 1) as i64 ^ -((value & 1) as i64));
+    }
+
+    Ok(program)
+}
+
+/// Writes a program in the binary format.
+pub fn encode_binary(program: &[i64]) -> Vec<u8> {
+    let mut bytes = BINARY_MAGIC.to_vec();
+
+    for value in program {
+        let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+
+        while zigzag >= 0x80 {
+            bytes.push((zigzag as u8 & 0x7f) | 0x80);
+            zigzag >>= 7;
+        }
+
+        bytes.push(zigzag as u8);
+    }
+
+    bytes
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::instructions::{self, Effect, InstructionSet, Operation, Role};
+use crate::io::{Input, Output};
+
+/// Memory grows on demand when a program writes beyond its end, but never beyond this many values.
+pub const MEMORY_LIMIT: usize = 1 << 22;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    WaitingForInput,
+    Halted,
+}
+
+/// Everything that can go wrong while running a program. `address` is always the address of the instruction
+/// that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnknownOpcode { address: usize, opcode: i64 },
+    UnknownMode { address: usize, mode: i64 },
+    ImmediateWrite { address: usize },
+    NegativeAddress { address: usize, target: i64 },
+    OutOfMemory { address: usize, target: i64 },
+    Overflow { address: usize },
+    /// An opcode that exists, but not in the machine's `Profile`.
+    UnsupportedOpcode { address: usize, opcode: i64 },
+    /// A parameter mode that exists, but not in the machine's `Profile`.
+    UnsupportedMode { address: usize, mode: i64 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownOpcode { address, opcode } => write!(f, "unknown opcode {} at {}", opcode, address),
+            Error::UnknownMode { address, mode } => write!(f, "unknown parameter mode {} at {}", mode, address),
+            Error::ImmediateWrite { address } => write!(f, "write in immediate mode at {}", address),
+            Error::NegativeAddress { address, target } => {
+                write!(f, "access to negative address {} at {}", target, address)
+            },
+            Error::OutOfMemory { address, target } => {
+                write!(f, "access to address {} beyond the memory limit at {}", target, address)
+            },
+            Error::Overflow { address } => write!(f, "integer overflow at {}", address),
+            Error::UnsupportedOpcode { address, opcode } => {
+                write!(f, "opcode {} at {} is not supported by this machine", opcode, address)
+            },
+            Error::UnsupportedMode { address, mode } => {
+                write!(f, "parameter mode {} at {} is not supported by this machine", mode, address)
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An address at or beyond `MEMORY_LIMIT`, given to `Machine::set`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeyondLimit {
+    pub address: usize,
+}
+
+impl fmt::Display for BeyondLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "address {} is beyond the memory limit of {}", self.address, MEMORY_LIMIT)
+    }
+}
+
+impl std::error::Error for BeyondLimit {}
+
+/// The instructions and parameter modes a machine understands. The computer grew over the puzzles, and older
+/// profiles behave exactly like it did back then.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Opcodes 1, 2 and 99 in position mode.
+    Day02,
+    /// Adds opcodes 3 to 8 and immediate mode.
+    Day05,
+    /// Adds opcode 9 and relative mode.
+    #[default]
+    Full,
+    Custom { opcodes: Vec<i64>, modes: Vec<i64> },
+}
+
+impl Profile {
+    pub fn allows_opcode(&self, opcode: i64) -> bool {
+        match self {
+            Profile::Day02 => [1, 2, 99].contains(&opcode),
+            Profile::Day05 => (1..=8).contains(&opcode) || opcode == 99,
+            Profile::Full => true,
+            Profile::Custom { opcodes, .. } => opcodes.contains(&opcode),
+        }
+    }
+
+    pub fn allows_mode(&self, mode: i64) -> bool {
+        match self {
+            Profile::Day02 => mode == 0,
+            Profile::Day05 => mode <= 1,
+            Profile::Full => true,
+            Profile::Custom { modes, .. } => modes.contains(&mode),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+    memory: Vec<i64>,
+    ip: usize,
+    pub(crate) relative_base: i64,
+    halted: bool,
+    profile: Profile,
+    instructions: Arc<InstructionSet>,
+}
+
+impl Machine {
+    pub fn new(program: &[i64]) -> Machine {
+        Machine {
+            memory: program.to_vec(),
+            ip: 0,
+            relative_base: 0,
+            halted: false,
+            profile: Profile::Full,
+            instructions: Arc::clone(instructions::shared()),
+        }
+    }
+
+    /// Replaces the standard instructions, for example with a set that has extra ones registered.
+    pub fn with_instructions(mut self, instructions: InstructionSet) -> Machine {
+        self.instructions = Arc::new(instructions);
+        self
+    }
+
+    pub fn instructions(&self) -> &InstructionSet {
+        &self.instructions
+    }
+
+    /// Restricts the machine to the instructions and parameter modes of the profile.
+    pub fn with_profile(mut self, profile: Profile) -> Machine {
+        self.profile = profile;
+        self
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    // Moves the instruction pointer, like a debugger writing to it.
+    pub(crate) fn set_ip(&mut self, ip: usize) {
+        self.ip = ip;
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// The memory that has been initialized or written so far. Everything beyond it reads as 0.
+    pub fn memory(&self) -> &[i64] {
+        &self.memory
+    }
+
+    pub fn get(&self, address: usize) -> i64 {
+        self.memory.get(address).cloned().unwrap_or(0)
+    }
+
+    /// Overwrites a memory cell before or between runs, like replacing the noun and verb on day 2. Memory grows
+    /// to the address, as long as it stays below `MEMORY_LIMIT`.
+    pub fn set(&mut self, address: usize, value: i64) -> Result<(), BeyondLimit> {
+        if address >= MEMORY_LIMIT {
+            return Err(BeyondLimit { address });
+        }
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = value;
+        Ok(())
+    }
+
+    /// The address the instruction at the instruction pointer writes to, if it writes anywhere and can be
+    /// decoded. An input instruction only writes once there is an input.
+    pub fn write_target(&self) -> Option<usize> {
+        let instruction = self.get(self.ip);
+        let definition = self.instructions.get(instruction % 100)?;
+        let n = definition.roles.iter().position(|role| *role == Role::Write)?;
+
+        self.target(instruction, n as u32 + 1).ok()
+    }
+
+    /// Runs until the program halts or waits for an input that isn't available yet.
+    pub fn run<I, O>(&mut self, input: &mut I, output: &mut O) -> Result<State, Error>
+    where
+        I: Input + ?Sized,
+        O: Output + ?Sized,
+    {
+        loop {
+            match self.step(input, output)? {
+                State::Running => continue,
+                state => return Ok(state),
+            }
+        }
+    }
+
+    /// Executes a single instruction. If it is an input instruction and no input is available the instruction
+    /// pointer stays where it is, so the next step retries it.
+    pub fn step<I, O>(&mut self, mut input: &mut I, mut output: &mut O) -> Result<State, Error>
+    where
+        I: Input + ?Sized,
+        O: Output + ?Sized,
+    {
+        if self.halted {
+            return Ok(State::Halted);
+        }
+
+        let instruction = self.get(self.ip);
+        let instructions = Arc::clone(&self.instructions);
+        let definition = instructions.get(instruction % 100)
+            .ok_or(Error::UnknownOpcode { address: self.ip, opcode: instruction % 100 })?;
+
+        if !self.profile.allows_opcode(definition.opcode) {
+            return Err(Error::UnsupportedOpcode { address: self.ip, opcode: definition.opcode });
+        }
+
+        let mut operation = Operation { machine: self, instruction, input: &mut input, output: &mut output };
+
+        match definition.execute(&mut operation)? {
+            Effect::Next => self.ip += definition.roles.len() + 1,
+            Effect::Jump(destination) => self.ip = self.checked(destination)?,
+            Effect::Wait => return Ok(State::WaitingForInput),
+            Effect::Halt => {
+                self.halted = true;
+                return Ok(State::Halted);
+            },
+        }
+
+        Ok(State::Running)
+    }
+
+    fn mode(&self, instruction: i64, n: u32) -> Result<i64, Error> {
+        match instruction / 10_i64.pow(n + 1) % 10 {
+            mode @ 0..=2 if self.profile.allows_mode(mode) => Ok(mode),
+            mode @ 0..=2 => Err(Error::UnsupportedMode { address: self.ip, mode }),
+            mode => Err(Error::UnknownMode { address: self.ip, mode }),
+        }
+    }
+
+    // The value of the nth parameter of the current instruction.
+    pub(crate) fn parameter(&self, instruction: i64, n: u32) -> Result<i64, Error> {
+        let raw = self.get(self.ip + n as usize);
+
+        match self.mode(instruction, n)? {
+            0 => Ok(self.get(self.checked(raw)?)),
+            1 => Ok(raw),
+            _ => Ok(self.get(self.relative(raw)?)),
+        }
+    }
+
+    // The address the nth parameter of the current instruction writes to.
+    pub(crate) fn target(&self, instruction: i64, n: u32) -> Result<usize, Error> {
+        let raw = self.get(self.ip + n as usize);
+
+        match self.mode(instruction, n)? {
+            0 => self.checked(raw),
+            1 => Err(Error::ImmediateWrite { address: self.ip }),
+            _ => self.relative(raw),
+        }
+    }
+
+    fn relative(&self, raw: i64) -> Result<usize, Error> {
+        let target = self.relative_base.checked_add(raw).ok_or(Error::Overflow { address: self.ip })?;
+        self.checked(target)
+    }
+
+    fn checked(&self, target: i64) -> Result<usize, Error> {
+        if target < 0 {
+            Err(Error::NegativeAddress { address: self.ip, target })
+        } else if target as u64 >= MEMORY_LIMIT as u64 {
+            Err(Error::OutOfMemory { address: self.ip, target })
+        } else {
+            Ok(target as usize)
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::num::NonZeroUsize;
+
+use crate::machine::{Error, Machine, State};
+
+/// Where the outputs of a machine in a network go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// Every output value is sent to each of the listed machines, like the amplifiers on day 7.
+    Forward(Vec<usize>),
+    /// Outputs are grouped into packets of the given size. The first value of a packet is the address of the
+    /// machine that receives the remaining values.
+    Packets(usize),
+    /// Every output value ends up in the outbox of the network.
+    Outbox,
+}
+
+/// Values that left a machine but didn't reach another one, because they were routed to the outbox or to an
+/// address that isn't part of the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub source: usize,
+    pub address: Option<i64>,
+    pub values: Vec<i64>,
+}
+
+/// A single value passed from one machine to another, or out of the network if it has no destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transmission {
+    pub source: usize,
+    pub destination: Option<usize>,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every machine halted.
+    Halted,
+    /// The listed machines wait for input, but nobody is left to send them any.
+    Deadlock(Vec<usize>),
+    /// Machines halted in the middle of a packet, holding the address and the values they got out. This wins over
+    /// a deadlock, which is likely caused by the missing values.
+    Unfinished(Vec<Packet>),
+}
+
+/// A value sent from outside to an address no machine in the network has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownAddress {
+    pub address: usize,
+}
+
+impl fmt::Display for UnknownAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "there is no machine at address {}", self.address)
+    }
+}
+
+impl std::error::Error for UnknownAddress {}
+
+#[derive(Debug)]
+struct Node {
+    machine: Machine,
+    route: Route,
+    inbox: VecDeque<i64>,
+    pending: Vec<i64>,
+    last_output: Option<i64>,
+}
+
+/// A number of machines whose outputs are routed into each other's inputs.
+#[derive(Debug, Default)]
+pub struct Network {
+    nodes: Vec<Node>,
+    outbox: Vec<Packet>,
+    unfinished: Vec<Packet>,
+    slice: Option<NonZeroUsize>,
+    transmissions: Option<Vec<Transmission>>,
+}
+
+impl Network {
+    pub fn new() -> Network {
+        Network::default()
+    }
+
+    /// Adds a machine and returns its address in the network. Addresses are handed out in order, starting at 0.
+    pub fn add(&mut self, machine: Machine, route: Route) -> usize {
+        self.nodes.push(Node {
+            machine,
+            route,
+            inbox: VecDeque::new(),
+            pending: Vec::new(),
+            last_output: None,
+        });
+
+        self.nodes.len() - 1
+    }
+
+    /// Limits how many instructions a machine may execute per turn. Without a limit every machine runs until it
+    /// halts or waits for input.
+    pub fn set_slice(&mut self, slice: NonZeroUsize) {
+        self.slice = Some(slice);
+    }
+
+    /// Starts keeping a log of every value a machine sends from now on.
+    pub fn record_transmissions(&mut self) {
+        self.transmissions.get_or_insert_with(Vec::new);
+    }
+
+    /// The values sent since `record_transmissions` was called, in the order they were sent.
+    pub fn transmissions(&self) -> &[Transmission] {
+        self.transmissions.as_ref().map_or(&[], |t| t.as_slice())
+    }
+
+    /// Queues a value for the input of a machine.
+    pub fn send(&mut self, address: usize, value: i64) -> Result<(), UnknownAddress> {
+        let node = self.nodes.get_mut(address).ok_or(UnknownAddress { address })?;
+        node.inbox.push_back(value);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Panics if there is no machine at the address, like indexing a slice.
+    pub fn machine(&self, address: usize) -> &Machine {
+        &self.nodes[address].machine
+    }
+
+    /// The value most recently produced by a machine, wherever it was routed to. Panics if there is no machine
+    /// at the address.
+    pub fn last_output(&self, address: usize) -> Option<i64> {
+        self.nodes[address].last_output
+    }
+
+    pub fn outbox(&self) -> &[Packet] {
+        &self.outbox
+    }
+
+    /// Gives every machine one turn, in the order they were added. Returns whether any of them made progress.
+    pub fn round(&mut self) -> Result<bool, Error> {
+        let mut progress = false;
+
+        for address in 0..self.nodes.len() {
+            progress |= self.turn(address)?;
+        }
+
+        Ok(progress)
+    }
+
+    /// Runs rounds until every machine halted or no machine can make progress anymore.
+    pub fn run(&mut self) -> Result<Outcome, Error> {
+        while self.round()? {}
+
+        let waiting: Vec<usize> = (0..self.nodes.len())
+            .filter(|&address| !self.nodes[address].machine.is_halted())
+            .collect();
+
+        if !self.unfinished.is_empty() {
+            Ok(Outcome::Unfinished(self.unfinished.clone()))
+        } else if waiting.is_empty() {
+            Ok(Outcome::Halted)
+        } else {
+            Ok(Outcome::Deadlock(waiting))
+        }
+    }
+
+    // Runs a machine for a turn. Whatever it output before failing is still routed, and a packet it didn't finish
+    // before halting is kept for `run` to report.
+    fn turn(&mut self, address: usize) -> Result<bool, Error> {
+        let mut outputs = Vec::new();
+        let mut progress = false;
+        let mut failure = None;
+        let mut steps = 0;
+
+        {
+            let node = &mut self.nodes[address];
+
+            while self.slice.is_none_or(|slice| steps < slice.get()) {
+                let halted = node.machine.is_halted();
+
+                match node.machine.step(&mut node.inbox, &mut outputs) {
+                    Ok(State::Running) => progress = true,
+                    Ok(State::Halted) => {
+                        progress |= !halted;
+                        break;
+                    },
+                    Ok(State::WaitingForInput) => break,
+                    Err(e) => {
+                        failure = Some(e);
+                        break;
+                    },
+                }
+
+                steps += 1;
+            }
+        }
+
+        for value in outputs {
+            self.route(address, value);
+        }
+
+        let node = &mut self.nodes[address];
+
+        if node.machine.is_halted() && !node.pending.is_empty() {
+            let mut values = std::mem::take(&mut node.pending);
+            let destination = values.remove(0);
+            self.unfinished.push(Packet { source: address, address: Some(destination), values });
+        }
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(progress),
+        }
+    }
+
+    fn route(&mut self, source: usize, value: i64) {
+        self.nodes[source].last_output = Some(value);
+
+        match self.nodes[source].route.clone() {
+            Route::Forward(addresses) => {
+                if addresses.is_empty() {
+                    self.log(source, None, value);
+                }
+
+                for address in addresses {
+                    self.deliver(source, address as i64, vec![value]);
+                }
+            },
+            Route::Packets(size) => {
+                let pending = &mut self.nodes[source].pending;
+                pending.push(value);
+
+                if pending.len() >= size {
+                    let mut values = std::mem::take(pending);
+                    let address = values.remove(0);
+                    self.deliver(source, address, values);
+                }
+            },
+            Route::Outbox => {
+                self.log(source, None, value);
+                self.outbox.push(Packet { source, address: None, values: vec![value] });
+            },
+        }
+    }
+
+    fn deliver(&mut self, source: usize, address: i64, values: Vec<i64>) {
+        let destination = if address >= 0 && (address as usize) < self.nodes.len() {
+            Some(address as usize)
+        } else {
+            None
+        };
+
+        for value in &values {
+            self.log(source, destination, *value);
+        }
+
+        match destination {
+            Some(destination) => self.nodes[destination].inbox.extend(values),
+            None => self.outbox.push(Packet { source, address: Some(address), values }),
+        }
+    }
+
+    fn log(&mut self, source: usize, destination: Option<usize>, value: i64) {
+        if let Some(transmissions) = &mut self.transmissions {
+            transmissions.push(Transmission { source, destination, value });
+        }
+    }
+}
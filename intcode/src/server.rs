@@ -0,0 +1,293 @@
+// Running machines on behalf of other programs, which talk to the server over a Unix socket or a TCP connection.
+// Every request is a JSON object on a line of its own and gets a JSON object on a line back:
+//
+//     > {"command": "load", "program": "3,0,4,0,99"}
+//     < {"ok": true, "program": 1}
+//     > {"command": "create", "program": 1}
+//     < {"ok": true, "machine": 1}
+//     > {"command": "run", "machine": 1, "input": [42]}
+//     < {"ok": true, "state": "halted", "outputs": [42]}
+//
+// The commands are
+//
+// - `load` with a `program`, either a list of values or text the way the puzzle inputs come,
+// - `create` with the `program` a new machine runs,
+// - `input` with a `machine` and the `values` to queue for it,
+// - `run` with a `machine`, optionally `input` to queue first and `steps` to limit how many instructions run,
+//   a million unless given. It answers with everything the machine output and its `state`: "halted", "waiting"
+//   for input or "running" if it ran out of steps,
+// - `snapshot` with a `machine`, answering with its `ip`, `relative_base`, whether it `halted`, its `memory` and
+//   the `input` it didn't read yet,
+// - `destroy` with a `machine`.
+//
+// Failures answer with `"ok": false` and an `error`. If the machine failed while running, the answer also has the
+// `outputs` up to the failure. An `id` in a request is sent back in its response. A request longer than
+// `MAX_REQUEST` bytes is answered with an error, and the connection is closed. Programs and machines belong to
+// the server, not to a connection, so any client can use them. Each client is served on a thread of its own.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::os::unix::net::UnixListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Map, Value};
+
+use crate::machine::{Machine, State};
+
+/// How many instructions `run` executes if the request doesn't say, so a machine in an endless loop doesn't keep
+/// the client waiting forever.
+pub const DEFAULT_STEPS: u64 = 1_000_000;
+
+/// How many bytes a request may take up, not counting the line break.
+pub const MAX_REQUEST: usize = 1 << 20;
+
+// How long to wait before accepting connections again after it failed, like when the server ran out of file
+// descriptors.
+const BACKOFF: Duration = Duration::from_millis(100);
+
+// A machine and the input it didn't read yet.
+#[derive(Debug)]
+struct Hosted {
+    machine: Machine,
+    input: VecDeque<i64>,
+}
+
+#[derive(Debug, Default)]
+pub struct Server {
+    programs: Mutex<HashMap<u64, Arc<Vec<i64>>>>,
+    machines: Mutex<HashMap<u64, Arc<Mutex<Hosted>>>>,
+    next: AtomicU64,
+}
+
+// Why a request failed, and whatever else the answer should carry.
+struct Failure {
+    error: String,
+    response: Map<String, Value>,
+}
+
+impl From<String> for Failure {
+    fn from(error: String) -> Failure {
+        Failure { error, response: Map::new() }
+    }
+}
+
+impl From<&str> for Failure {
+    fn from(error: &str) -> Failure {
+        Failure::from(error.to_string())
+    }
+}
+
+fn field<'a>(request: &'a Map<String, Value>, name: &str) -> Result<&'a Value, String> {
+    request.get(name).ok_or_else(|| format!("missing {}", name))
+}
+
+fn id(request: &Map<String, Value>, name: &str) -> Result<u64, String> {
+    field(request, name)?.as_u64().ok_or_else(|| format!("{} is not an id", name))
+}
+
+fn values(value: &Value, name: &str) -> Result<Vec<i64>, String> {
+    match value {
+        Value::Array(values) => values.iter().map(|v| v.as_i64().ok_or_else(|| format!("{} has to be numbers", name))).collect(),
+        _ => Err(format!("{} is not a list", name)),
+    }
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    /// Answers a single request.
+    pub fn handle(&self, line: &str) -> String {
+        let request: Map<String, Value> = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return json!({ "ok": false, "error": e.to_string() }).to_string(),
+        };
+
+        let mut response = match self.answer(&request) {
+            Ok(mut response) => {
+                response.insert("ok".to_string(), Value::Bool(true));
+                response
+            },
+            Err(Failure { error, mut response }) => {
+                response.insert("ok".to_string(), Value::Bool(false));
+                response.insert("error".to_string(), Value::String(error));
+                response
+            },
+        };
+
+        if let Some(id) = request.get("id") {
+            response.insert("id".to_string(), id.clone());
+        }
+
+        Value::Object(response).to_string()
+    }
+
+    fn answer(&self, request: &Map<String, Value>) -> Result<Map<String, Value>, Failure> {
+        let command = field(request, "command")?.as_str().ok_or("command is not a string")?;
+        let mut response = Map::new();
+
+        match command {
+            "load" => {
+                let program = match field(request, "program")? {
+                    Value::String(text) => crate::loader::parse(text).map_err(|e| e.to_string())?,
+                    value => values(value, "program")?,
+                };
+
+                let id = self.next_id();
+                self.programs.lock().unwrap().insert(id, Arc::new(program));
+                response.insert("program".to_string(), json!(id));
+            },
+            "create" => {
+                let program = id(request, "program")?;
+                let program = self.programs.lock().unwrap().get(&program).cloned()
+                    .ok_or_else(|| format!("there is no program {}", program))?;
+
+                let id = self.next_id();
+                let hosted = Hosted { machine: Machine::new(&program), input: VecDeque::new() };
+                self.machines.lock().unwrap().insert(id, Arc::new(Mutex::new(hosted)));
+                response.insert("machine".to_string(), json!(id));
+            },
+            "input" => {
+                let values = values(field(request, "values")?, "values")?;
+                self.machine(request)?.lock().unwrap().input.extend(values);
+            },
+            "run" => {
+                let input = match request.get("input") {
+                    Some(value) => values(value, "input")?,
+                    None => Vec::new(),
+                };
+                let steps = match request.get("steps") {
+                    Some(steps) => steps.as_u64().ok_or("steps is not a number")?,
+                    None => DEFAULT_STEPS,
+                };
+
+                let machine = self.machine(request)?;
+                let mut hosted = machine.lock().unwrap();
+                let Hosted { machine, input: queue } = &mut *hosted;
+                queue.extend(input);
+
+                let mut outputs = Vec::new();
+                let mut executed = 0;
+
+                let state = loop {
+                    if executed == steps {
+                        break "running";
+                    }
+
+                    match machine.step(queue, &mut outputs) {
+                        Ok(State::Running) => executed += 1,
+                        Ok(State::Halted) => break "halted",
+                        Ok(State::WaitingForInput) => break "waiting",
+                        Err(e) => {
+                            response.insert("outputs".to_string(), json!(outputs));
+                            return Err(Failure { error: e.to_string(), response });
+                        },
+                    }
+                };
+
+                response.insert("state".to_string(), json!(state));
+                response.insert("outputs".to_string(), json!(outputs));
+            },
+            "snapshot" => {
+                let machine = self.machine(request)?;
+                let hosted = machine.lock().unwrap();
+
+                response.insert("ip".to_string(), json!(hosted.machine.ip()));
+                response.insert("relative_base".to_string(), json!(hosted.machine.relative_base()));
+                response.insert("halted".to_string(), json!(hosted.machine.is_halted()));
+                response.insert("memory".to_string(), json!(hosted.machine.memory()));
+                response.insert("input".to_string(), json!(hosted.input));
+            },
+            "destroy" => {
+                let machine = id(request, "machine")?;
+                self.machines.lock().unwrap().remove(&machine)
+                    .ok_or_else(|| format!("there is no machine {}", machine))?;
+            },
+            command => return Err(format!("unknown command {}", command).into()),
+        }
+
+        Ok(response)
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn machine(&self, request: &Map<String, Value>) -> Result<Arc<Mutex<Hosted>>, String> {
+        let machine = id(request, "machine")?;
+        self.machines.lock().unwrap().get(&machine).cloned().ok_or_else(|| format!("there is no machine {}", machine))
+    }
+
+    /// Answers requests until the client hangs up or sends a request longer than `MAX_REQUEST`.
+    pub fn serve<R: BufRead, W: Write>(&self, mut reader: R, mut writer: W) -> io::Result<()> {
+        loop {
+            let mut line = Vec::new();
+
+            if reader.by_ref().take(MAX_REQUEST as u64 + 1).read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+
+            // The rest of the line can't be told apart from the next request, so there is no carrying on.
+            if line.len() > MAX_REQUEST && !line.ends_with(b"\n") {
+                let error = format!("requests can be at most {} bytes long", MAX_REQUEST);
+                writeln!(writer, "{}", json!({ "ok": false, "error": error }))?;
+                return writer.flush();
+            }
+
+            let line = String::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            writeln!(writer, "{}", self.handle(line.trim_end_matches(&['\r', '\n'][..])))?;
+            writer.flush()?;
+        }
+    }
+
+    /// Serves every client connecting to the listener on a thread of its own, forever. Connections that fail to
+    /// be accepted are reported on stderr and don't stop the server.
+    pub fn listen_tcp(self: Arc<Self>, listener: TcpListener) -> ! {
+        loop {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("accepting a connection failed: {}", e);
+                    thread::sleep(BACKOFF);
+                    continue;
+                },
+            };
+            let server = Arc::clone(&self);
+
+            thread::spawn(move || {
+                let reader = BufReader::new(stream.try_clone()?);
+                server.serve(reader, stream)
+            });
+        }
+    }
+
+    /// Same as `listen_tcp`, for a Unix socket.
+    pub fn listen_unix(self: Arc<Self>, listener: UnixListener) -> ! {
+        loop {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("accepting a connection failed: {}", e);
+                    thread::sleep(BACKOFF);
+                    continue;
+                },
+            };
+            let server = Arc::clone(&self);
+
+            thread::spawn(move || {
+                let reader = BufReader::new(stream.try_clone()?);
+                server.serve(reader, stream)
+            });
+        }
+    }
+}
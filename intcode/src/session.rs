@@ -0,0 +1,235 @@
+// Recording every input a machine reads, together with everything it output since the input before, so a
+// session can be played back later and gives the same outputs again. Sessions are saved as text, one line per
+// input and one per run of outputs:
+//
+//     < 63,10
+//     > 72
+//     > 105
+//     > 10
+//     < 1234
+//
+// Lines starting with "<" are what the machine output, here a question mark and a line break. Lines starting
+// with ">" are the values sent to it, one each, here the line "Hi". Outputs after the last input come last.
+
+use std::fmt;
+use std::mem;
+
+use crate::io::{Input, Output};
+use crate::machine::{Error, Machine, State};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// What the machine output since the input before this one.
+    pub outputs: Vec<i64>,
+    pub input: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub entries: Vec<Entry>,
+    /// What the machine output after the last input.
+    pub outputs: Vec<i64>,
+}
+
+impl Session {
+    /// The session up to input number `inputs`, with the outputs right before it, the way a replay stopped
+    /// there left the machine.
+    pub fn prefix(&self, inputs: usize) -> Session {
+        let entries = self.entries[..inputs.min(self.entries.len())].to_vec();
+        let outputs = self.entries.get(inputs).map_or(&self.outputs, |entry| &entry.outputs).clone();
+
+        Session { entries, outputs }
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = i64> + '_ {
+        self.entries.iter().map(|entry| entry.input)
+    }
+
+    pub fn parse(text: &str) -> Result<Session, String> {
+        let mut session = Session::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let values = |list: &str| {
+                list.split(',').filter(|v| !v.trim().is_empty()).map(|v| v.trim().parse::<i64>()).collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| format!("line {}: cannot understand \"{}\"", i + 1, line))
+            };
+
+            if line.is_empty() {
+                continue;
+            } else if let Some(outputs) = line.strip_prefix('<') {
+                session.outputs.extend(values(outputs)?);
+            } else if let Some(input) = line.strip_prefix('>') {
+                match values(input)?.as_slice() {
+                    [input] => session.entries.push(Entry { outputs: mem::take(&mut session.outputs), input: *input }),
+                    _ => return Err(format!("line {}: expected a single input", i + 1)),
+                }
+            } else {
+                return Err(format!("line {}: cannot understand \"{}\"", i + 1, line));
+            }
+        }
+
+        Ok(session)
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |values: &[i64]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+
+        for entry in &self.entries {
+            if !entry.outputs.is_empty() {
+                writeln!(f, "< {}", list(&entry.outputs))?;
+            }
+            writeln!(f, "> {}", entry.input)?;
+        }
+
+        if !self.outputs.is_empty() {
+            writeln!(f, "< {}", list(&self.outputs))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Passes inputs and outputs through, remembering them for the recorder.
+struct Tap<'a, T: ?Sized> {
+    inner: &'a mut T,
+    seen: Vec<i64>,
+}
+
+impl<'a, T: Input + ?Sized> Input for Tap<'a, T> {
+    fn read(&mut self) -> Option<i64> {
+        let value = self.inner.read();
+        self.seen.extend(value);
+        value
+    }
+}
+
+impl<'a, T: Output + ?Sized> Output for Tap<'a, T> {
+    fn write(&mut self, value: i64) {
+        self.seen.push(value);
+        self.inner.write(value);
+    }
+}
+
+/// Runs machines and records what goes in and out of them.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    session: Session,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    /// Carries on recording a session, like one that was just replayed.
+    pub fn resume(session: Session) -> Recorder {
+        Recorder { session }
+    }
+
+    /// Same as `Machine::run`, recording along the way.
+    pub fn run<I, O>(&mut self, machine: &mut Machine, input: &mut I, output: &mut O) -> Result<State, Error>
+    where
+        I: Input + ?Sized,
+        O: Output + ?Sized,
+    {
+        loop {
+            let mut input = Tap { inner: &mut *input, seen: Vec::new() };
+            let mut output = Tap { inner: &mut *output, seen: Vec::new() };
+            let state = machine.step(&mut input, &mut output);
+
+            self.session.outputs.extend(output.seen);
+
+            for value in input.seen {
+                self.session.entries.push(Entry { outputs: mem::take(&mut self.session.outputs), input: value });
+            }
+
+            match state? {
+                State::Running => continue,
+                state => return Ok(state),
+            }
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn into_session(self) -> Session {
+        self.session
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Machine(Error),
+    /// The outputs before input number `input` aren't the recorded ones. `input` is the number of entries when
+    /// it's about the outputs after the last input.
+    Diverged { input: usize, expected: Vec<i64>, actual: Vec<i64> },
+    /// The program halted before reading input number `input`.
+    Halted { input: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Machine(e) => write!(f, "{}", e),
+            ReplayError::Diverged { input, expected, actual } => {
+                write!(f, "output {:?} instead of {:?} before input {}", actual, expected, input)
+            },
+            ReplayError::Halted { input } => write!(f, "halted before input {}", input),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<Error> for ReplayError {
+    fn from(e: Error) -> ReplayError {
+        ReplayError::Machine(e)
+    }
+}
+
+#[derive(Debug)]
+pub struct Replayed {
+    pub machine: Machine,
+    pub state: State,
+    /// How many of the recorded inputs were read.
+    pub inputs: usize,
+    /// Everything the machine output along the way, up to where the replay ended.
+    pub outputs: Vec<i64>,
+}
+
+/// Plays a session back on a fresh machine, checking every output along the way. With `stop`, the machine is
+/// handed back just before it reads input number `stop`, waiting for it, so it can be looked at.
+pub fn replay(program: &[i64], session: &Session, stop: Option<usize>) -> Result<Replayed, ReplayError> {
+    let mut machine = Machine::new(program);
+    let mut pending = None;
+    let mut all = Vec::new();
+
+    for i in 0..=session.entries.len() {
+        let mut outputs = Vec::new();
+        let state = machine.run(&mut pending, &mut outputs)?;
+        let expected = session.entries.get(i).map_or(&session.outputs, |entry| &entry.outputs);
+
+        if outputs != *expected {
+            return Err(ReplayError::Diverged { input: i, expected: expected.clone(), actual: outputs });
+        }
+
+        all.extend(outputs);
+
+        if i == session.entries.len() || stop == Some(i) {
+            return Ok(Replayed { machine, state, inputs: i, outputs: all });
+        }
+
+        if state == State::Halted {
+            return Err(ReplayError::Halted { input: i });
+        }
+
+        pending = Some(session.entries[i].input);
+    }
+
+    unreachable!()
+}
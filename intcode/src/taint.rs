@@ -0,0 +1,163 @@
+// Following the inputs of a program through its memory. Every cell carries the set of inputs its value depends
+// on, numbering the inputs from 0 in the order they were read, and every output reports the inputs it depends on:
+//
+//     let mut taint = Taint::new();
+//     taint.run(&mut machine, &mut inputs, &mut Vec::new())?;
+//     for output in taint.outputs() {
+//         println!("{} depends on inputs {:?}", output.value, output.sources);
+//     }
+//
+// Instructions are only known by the roles of their parameters, so extra instructions are tracked just like the
+// ones from the puzzles. Whatever an instruction writes or outputs depends on everything it reads, including the
+// pointers it reads through and the relative base, and on the input it consumed. An instruction that reads but
+// neither writes nor outputs anything, like a jump, decides where the program goes next, so from then on
+// everything depends on what it read. That is coarser than it has to be, a branch only matters until both ways
+// meet again, but no input that influenced a value is ever missed.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::instructions::Role;
+use crate::io::{Counted, Input, Output};
+use crate::machine::{Error, Machine, State};
+
+/// An output value and the inputs it depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub value: i64,
+    pub sources: BTreeSet<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Taint {
+    cells: HashMap<usize, BTreeSet<usize>>,
+    relative_base: BTreeSet<usize>,
+    control: BTreeSet<usize>,
+    inputs: usize,
+    outputs: Vec<Provenance>,
+}
+
+// Passes outputs on, keeping a copy.
+struct Copied<'a, O: ?Sized> {
+    output: &'a mut O,
+    values: Vec<i64>,
+}
+
+impl<'a, O: Output + ?Sized> Output for Copied<'a, O> {
+    fn write(&mut self, value: i64) {
+        self.values.push(value);
+        self.output.write(value);
+    }
+}
+
+impl Taint {
+    pub fn new() -> Taint {
+        Taint::default()
+    }
+
+    /// The inputs the value of a cell depends on.
+    pub fn sources(&self, address: usize) -> BTreeSet<usize> {
+        self.cells.get(&address).cloned().unwrap_or_default()
+    }
+
+    /// The inputs that decided which way the program went so far.
+    pub fn control(&self) -> &BTreeSet<usize> {
+        &self.control
+    }
+
+    /// How many inputs were read so far.
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// Every output so far, in order.
+    pub fn outputs(&self) -> &[Provenance] {
+        &self.outputs
+    }
+
+    /// Same as `Machine::run`, following the inputs along the way.
+    pub fn run<I, O>(&mut self, machine: &mut Machine, input: &mut I, output: &mut O) -> Result<State, Error>
+    where
+        I: Input + ?Sized,
+        O: Output + ?Sized,
+    {
+        loop {
+            let ip = machine.ip();
+            let relative_base = machine.relative_base();
+
+            // Which instruction runs depends on the instruction itself.
+            self.control.extend(self.sources(ip));
+
+            let (mut reads, targets) = self.operands(machine);
+            let mut counted = Counted { input: &mut *input, read: 0 };
+            let mut copied = Copied { output: &mut *output, values: Vec::new() };
+
+            match machine.step(&mut counted, &mut copied)? {
+                State::Running => (),
+                state => return Ok(state),
+            }
+
+            reads.extend(self.inputs..self.inputs + counted.read);
+            self.inputs += counted.read;
+
+            for target in &targets {
+                if reads.is_empty() {
+                    self.cells.remove(target);
+                } else {
+                    self.cells.insert(*target, reads.clone());
+                }
+            }
+
+            for value in copied.values.iter() {
+                self.outputs.push(Provenance { value: *value, sources: reads.clone() });
+            }
+
+            if targets.is_empty() && copied.values.is_empty() {
+                if machine.relative_base() != relative_base {
+                    self.relative_base.extend(reads);
+                } else {
+                    self.control.extend(reads);
+                }
+            }
+        }
+    }
+
+    // The inputs everything the instruction at the instruction pointer reads depends on, and the addresses it
+    // writes to.
+    fn operands(&self, machine: &Machine) -> (BTreeSet<usize>, Vec<usize>) {
+        let ip = machine.ip();
+        let instruction = machine.get(ip);
+        let mut reads = self.control.clone();
+        let mut targets = Vec::new();
+
+        let roles = match machine.instructions().get(instruction % 100) {
+            Some(definition) => definition.roles.clone(),
+            None => return (reads, targets),
+        };
+
+        for (n, role) in roles.iter().enumerate() {
+            let parameter = ip + n + 1;
+            let raw = machine.get(parameter);
+            reads.extend(self.sources(parameter));
+
+            let address = match instruction / 10_i64.pow(n as u32 + 2) % 10 {
+                0 => raw,
+                2 => {
+                    reads.extend(&self.relative_base);
+                    machine.relative_base().saturating_add(raw)
+                },
+                _ => continue,
+            };
+
+            if address < 0 {
+                continue;
+            }
+
+            match role {
+                Role::Read => reads.extend(self.sources(address as usize)),
+                Role::Write => targets.push(address as usize),
+            }
+        }
+
+        (reads, targets)
+    }
+}
@@ -0,0 +1,20 @@
+use intcode::ascii::{Ascii, AsciiInput, AsciiOutput};
+use intcode::{Machine, State};
+
+// Echoes a line character by character and then prints 1234.
+const ECHO: &[i64] = &[3, 100, 4, 100, 1008, 100, 10, 101, 1006, 101, 0, 104, 1234, 99];
+
+#[test]
+fn lines_go_in_and_text_comes_out() {
+    let mut machine = Machine::new(ECHO);
+    let mut input = AsciiInput::new();
+    let mut output = AsciiOutput::new();
+
+    assert_eq!(machine.run(&mut input, &mut output), Ok(State::WaitingForInput));
+
+    input.push_line("WALK\n");
+
+    assert_eq!(machine.run(&mut input, &mut output), Ok(State::Halted));
+    assert_eq!(output.take(), vec![Ascii::Text("WALK\n".to_string()), Ascii::Value(1234)]);
+    assert!(output.take().is_empty());
+}
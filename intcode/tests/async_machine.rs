@@ -0,0 +1,90 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use intcode::async_machine::{block_on, channel, join_all, Sender};
+use intcode::{AsyncIntcode, AsyncOutput, Machine, State};
+
+// Forwards signals to the next amplifier and remembers the last one.
+struct Wire {
+    sender: Sender,
+    last: Option<i64>,
+}
+
+impl AsyncOutput for Wire {
+    fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()> {
+        self.last = Some(value);
+        self.sender.poll_write(cx, value)
+    }
+}
+
+struct Collect(Vec<i64>);
+
+impl AsyncOutput for Collect {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.0.push(value);
+        Poll::Ready(())
+    }
+}
+
+fn feedback_loop(program: &[i64], phases: &[i64]) -> i64 {
+    let (senders, receivers): (Vec<_>, Vec<_>) = phases.iter().map(|_| channel()).unzip();
+
+    for (sender, phase) in senders.iter().zip(phases) {
+        sender.send(*phase);
+    }
+
+    senders[0].send(0);
+
+    let amplifiers: Vec<Pin<Box<dyn Future<Output = Option<i64>>>>> = receivers.into_iter().enumerate()
+        .map(|(i, input)| {
+            let mut output = Wire { sender: senders[(i + 1) % phases.len()].clone(), last: None };
+            let mut amplifier = AsyncIntcode::new(Machine::new(program));
+
+            Box::pin(async move {
+                assert_eq!(amplifier.run(input, &mut output).await, Ok(State::Halted));
+                output.last
+            }) as Pin<Box<dyn Future<Output = Option<i64>>>>
+        })
+        .collect();
+
+    drop(senders);
+
+    let outputs = block_on(join_all(amplifiers));
+    outputs[phases.len() - 1].unwrap()
+}
+
+#[test]
+fn feedback_loop_first_example() {
+    let program = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26,
+        27, 4, 27, 1001, 28, -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    assert_eq!(feedback_loop(&program, &[9, 8, 7, 6, 5]), 139629729);
+}
+
+#[test]
+fn feedback_loop_second_example() {
+    let program = [
+        3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54,
+        -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4,
+        53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+    ];
+
+    assert_eq!(feedback_loop(&program, &[9, 7, 8, 5, 6]), 18216);
+}
+
+#[test]
+fn closed_input_leaves_the_machine_waiting() {
+    let (sender, receiver) = channel();
+    let mut outputs = Collect(Vec::new());
+    let mut machine = AsyncIntcode::new(Machine::new(&[3, 0, 4, 0, 3, 0, 99]));
+
+    sender.send(42);
+    drop(sender);
+
+    assert_eq!(block_on(machine.run(receiver, &mut outputs)), Ok(State::WaitingForInput));
+    assert_eq!(machine.machine().ip(), 4);
+    assert_eq!(outputs.0, vec![42]);
+}
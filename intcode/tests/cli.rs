@@ -0,0 +1,56 @@
+use std::fs;
+use std::process::{Command, Output};
+
+fn intcode(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_intcode")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn cells_are_set_before_and_printed_after_the_run() {
+    let output = intcode(&["--set", "1=12", "--set", "2=2", "--print", "0,1", "../day02/input.txt"]);
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "0: 8017076\n1: 12\n");
+
+    let output = intcode(&["--set", &format!("{}=1", intcode::MEMORY_LIMIT), "../day02/input.txt"]);
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn heapdiff_refuses_cells_beyond_the_memory_limit() {
+    let output = Command::new(env!("CARGO_BIN_EXE_heapdiff"))
+        .args(["--set", &format!("{}=1", usize::MAX), "../day02/input.txt"]).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stderr),
+        format!("address {} is beyond the memory limit of {}\n", usize::MAX, intcode::MEMORY_LIMIT));
+}
+
+#[test]
+fn runs_stop_after_the_given_steps() {
+    let output = intcode(&["--steps", "1", "--print", "3", "../day02/input.txt"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "3: 3\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "stopped after 1 steps at 4\n");
+}
+
+#[test]
+fn outputs_are_printed_in_the_given_format() {
+    let output = intcode(&["--input", "1", "--format", "csv", "../day05/input.txt"]);
+
+    assert_eq!(stdout(&output), "0,0,0,0,0,0,0,0,0,11193703\n");
+
+    let path = std::env::temp_dir().join(format!("intcode-cli-{}.txt", std::process::id()));
+    fs::write(&path, "104,72,104,105,104,10,104,1000,99").unwrap();
+    let output = intcode(&["--format", "ascii", path.to_str().unwrap()]);
+    fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Hi\n[1000]\n");
+}
@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+use intcode::compiler::{compile, CompileError};
+use intcode::{Machine, State};
+
+fn run(source: &str, inputs: &[i64]) -> Vec<i64> {
+    let program = compile(source).unwrap_or_else(|e| panic!("{}", e));
+    let mut machine = Machine::new(&program);
+    let mut outputs = Vec::new();
+
+    assert_eq!(machine.run(&mut inputs.iter().cloned().collect::<VecDeque<_>>(), &mut outputs), Ok(State::Halted));
+
+    outputs
+}
+
+const FACTORIAL: &str = "
+    fn factorial(n) {
+        if n < 2 {
+            return 1;
+        }
+        return n * factorial(n - 1);
+    }
+
+    fn main() {
+        output(factorial(input()));
+    }
+";
+
+const FIBONACCI: &str = "
+    // the first n fibonacci numbers, the slow way
+    fn fibonacci(n) {
+        if n <= 1 {
+            return n;
+        }
+        return fibonacci(n - 1) + fibonacci(n - 2);
+    }
+
+    fn main() {
+        let n = input();
+        let i = 0;
+        while i < n {
+            output(fibonacci(i));
+            i = i + 1;
+        }
+    }
+";
+
+const ECHO: &str = "
+    // echoes inputs until a 0 comes in, negating the odd ones above -100
+    fn odd(n) {
+        while n > 1 || n < -1 {
+            if n > 0 { n = n - 2; } else { n = n + 2; }
+        }
+        return n != 0;
+    }
+
+    fn main() {
+        let value = input();
+        while !(value == 0) {
+            if odd(value) && value >= -100 {
+                output(-value);
+            } else {
+                output(value);
+            }
+            value = input();
+        }
+    }
+";
+
+#[test]
+fn factorial() {
+    assert_eq!(run(FACTORIAL, &[0]), vec![1]);
+    assert_eq!(run(FACTORIAL, &[10]), vec![3628800]);
+    assert_eq!(run(FACTORIAL, &[20]), vec![2432902008176640000]);
+}
+
+#[test]
+fn fibonacci() {
+    assert_eq!(run(FIBONACCI, &[10]), vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+}
+
+#[test]
+fn echo() {
+    assert_eq!(run(ECHO, &[4, 7, -3, -101, 12, 0, 5]), vec![4, -7, 3, -101, 12]);
+}
+
+#[test]
+fn logic_stops_as_soon_as_the_result_is_known() {
+    let source = "
+        fn main() {
+            let x = 0 && output(1);
+            let y = 2 || output(2);
+            let z = 3 && output(3) == 0;
+            output(x + y * 10 + z * 100);
+        }
+    ";
+
+    assert_eq!(run(source, &[]), vec![3, 110]);
+}
+
+#[test]
+fn mistakes_are_reported_with_their_line() {
+    let error = |source: &str| compile(source).unwrap_err();
+
+    assert_eq!(error("fn main() {\n  x = 1;\n}"), CompileError { line: 2, message: "unknown variable x".to_string() });
+    assert_eq!(error("fn main() {\n  output(f(1));\n}").message, "unknown function f");
+    assert_eq!(error("fn f(a) { return a; }\nfn main() { f(); }").message, "f takes 1 arguments, not 0");
+    assert_eq!(error("fn f() { }").message, "there is no main function");
+    assert_eq!(error("fn main() { let x = 4 / 2; }").message, "unexpected '/'");
+}
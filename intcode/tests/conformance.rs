@@ -0,0 +1,66 @@
+use std::panic;
+
+use intcode::{conformance, generator};
+use intcode::{engines, Engine, Error, Interpreter, State};
+
+#[test]
+fn every_engine_passes_the_conformance_suite() {
+    let failures: Vec<String> = engines().iter().flat_map(|engine| conformance::check(engine.as_ref())).collect();
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+// xorshift, good enough to come up with programs and the same ones every time.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self, n: usize) -> Vec<u8> {
+        (0..n).map(|_| self.next() as u8).collect()
+    }
+}
+
+#[test]
+fn random_programs_never_panic() {
+    let mut random = Random(0x2019_1202);
+
+    for _ in 0..5000 {
+        let generated = generator::generate(&random.bytes(256));
+        let (program, inputs) = (&generated.program, &generated.inputs);
+
+        let execution = panic::catch_unwind(|| Interpreter.execute(program, inputs, 1000))
+            .unwrap_or_else(|_| panic!("{:?} with inputs {:?} panicked", program, inputs));
+
+        match execution.result {
+            Ok(State::Halted) | Ok(State::WaitingForInput) | Ok(State::Running) => (),
+            Err(Error::UnknownOpcode { .. })
+            | Err(Error::UnknownMode { .. })
+            | Err(Error::ImmediateWrite { .. })
+            | Err(Error::NegativeAddress { .. })
+            | Err(Error::OutOfMemory { .. })
+            | Err(Error::Overflow { .. }) => (),
+            Err(e @ Error::UnsupportedOpcode { .. }) | Err(e @ Error::UnsupportedMode { .. }) => {
+                panic!("{:?} is not supported by the full profile: {}", program, e)
+            },
+        }
+    }
+}
+
+#[test]
+fn arbitrary_values_never_panic() {
+    let mut random = Random(0x2019_0905);
+
+    for _ in 0..5000 {
+        let program: Vec<i64> = (0..random.next() % 20 + 1).map(|_| random.next() as i64).collect();
+
+        if panic::catch_unwind(|| Interpreter.execute(&program, &[1, 2, 3], 1000)).is_err() {
+            panic!("{:?} panicked", program);
+        }
+    }
+}
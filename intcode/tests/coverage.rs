@@ -0,0 +1,54 @@
+use intcode::coverage::{Branch, Coverage};
+use intcode::disassembler::{self, Line};
+use intcode::{Machine, State};
+
+// The day 5 example that checks whether the input is 8, with jumps.
+const JUMP_POSITION: &[i64] = &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+
+#[test]
+fn programs_disassemble() {
+    let lines = disassembler::disassemble(JUMP_POSITION);
+
+    assert_eq!(lines[0].to_string(), "     0: in [12]");
+    assert_eq!(lines[1].to_string(), "     2: jz [12], [15]");
+    assert_eq!(lines[5], Line::Data { address: 12, value: -1 });
+    assert_eq!(disassembler::disassemble(&[109, -3, 21101, 1, 2, 0])[1].to_string(), "     2: add 1, 2, [rb+0]");
+}
+
+#[test]
+fn branches_are_counted_both_ways() {
+    let mut coverage = Coverage::new();
+
+    for input in &[0, 5, 7] {
+        let mut machine = Machine::new(JUMP_POSITION);
+        assert_eq!(coverage.run(&mut machine, &mut Some(*input), &mut Vec::new()), Ok(State::Halted));
+    }
+
+    assert_eq!(coverage.hits(0), 3);
+    assert_eq!(coverage.hits(5), 2);
+    assert_eq!(coverage.branch(2), Some(Branch { taken: 1, not_taken: 2 }));
+}
+
+#[test]
+fn jumps_to_the_next_instruction_are_taken() {
+    let mut coverage = Coverage::new();
+    let mut machine = Machine::new(&[1105, 1, 3, 1106, 1, 6, 99]);
+
+    assert_eq!(coverage.run(&mut machine, &mut None, &mut Vec::new()), Ok(State::Halted));
+    assert_eq!(coverage.branch(0), Some(Branch { taken: 1, not_taken: 0 }));
+    assert_eq!(coverage.branch(3), Some(Branch { taken: 0, not_taken: 1 }));
+}
+
+#[test]
+fn reports_show_what_never_ran() {
+    let mut coverage = Coverage::new();
+    let mut machine = Machine::new(JUMP_POSITION);
+    coverage.run(&mut machine, &mut Some(0), &mut Vec::new()).unwrap();
+
+    let report = coverage.report(JUMP_POSITION);
+    let lines: Vec<&str> = report.lines().collect();
+
+    assert_eq!(lines[1], "       1      2: jz [12], [15]    taken 1, not taken 0 (always taken)");
+    assert_eq!(lines[2], "       -      5: add [13], [14], [13]");
+    assert_eq!(lines.last(), Some(&"4 of 5 instructions executed, 1 branches only went one way"));
+}
@@ -0,0 +1,78 @@
+use intcode::coverage::Coverage;
+use intcode::decompiler::{decompile, decompile_traced};
+use intcode::generator;
+use intcode::{Machine, State};
+
+#[test]
+fn boost_has_a_recursive_function() {
+    let program = intcode::load("../day09/input.txt").unwrap();
+    let decompiled = decompile(&program);
+
+    assert!(decompiled.contains("\nfn f922(arg1) {\n    v63 = arg1 < 3\n    if !v63 {\n        callee[1] = arg1 - 1\n        call f922\n"));
+    assert!(decompiled.ends_with("        arg1 = callee[1] + local2\n    }\n    return\n}\n"));
+}
+
+#[test]
+fn jumps_become_ifs_and_loops() {
+    // Counts down from the input, printing every number, then prints whether it started out even.
+    let program = [
+        3, 100, 1001, 100, 0, 101, 4, 100, 101, -1, 100, 100, 1005, 100, 6,
+        1008, 101, 0, 102, 1005, 102, 25, 104, 1, 99, 104, 0, 99,
+    ];
+
+    assert_eq!(decompile(&program), "\
+fn main() {
+    v100 = input()
+    v101 = v100
+    loop {
+        output(v100)
+        v100 = v100 - 1
+        if !v100 { break }
+    }
+    v102 = v101 == 0
+    if !v102 {
+        output(1)
+        halt
+    }
+    output(0)
+    halt
+}
+");
+}
+
+#[test]
+fn jumps_into_the_middle_of_an_instruction_become_gotos() {
+    assert!(decompile(&[1205, -4, 2, 2]).contains("goto"));
+    assert!(decompile(&[1006, 8, 2, 1, 103, 1208, 1, -3, -1]).contains("goto l2"));
+}
+
+#[test]
+fn generated_programs_decompile() {
+    for seed in 0..20_000_u64 {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        let bytes: Vec<u8> = (0..200).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect();
+
+        decompile(&generator::generate(&bytes).program);
+    }
+}
+
+#[test]
+fn day05_decompiles_the_way_it_ran() {
+    let program = intcode::load("../day05/input.txt").unwrap();
+    let mut machine = Machine::new(&program);
+    let mut coverage = Coverage::new();
+
+    assert_eq!(coverage.run(&mut machine, &mut Some(1), &mut Vec::new()), Ok(State::Halted));
+
+    let decompiled = decompile_traced(machine.memory(), &coverage.executed().collect());
+
+    assert!(decompile(&program).lines().count() < 10);
+    assert!(decompiled.starts_with("fn main() {\n    v225 = input()\n"));
+    assert!(decompiled.contains("    v224 = mem[191] + 50\n    v224 = v224 - 64\n    output(v224)\n"));
+    assert!(decompiled.lines().filter(|line| line.trim_start().starts_with("output(")).count() >= 10);
+}
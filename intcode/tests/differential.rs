@@ -0,0 +1,71 @@
+use intcode::differential;
+use intcode::{engines, generator, Engine, Execution, Interpreter};
+
+const BUDGET: usize = 1_000_000;
+
+fn load(path: &str) -> Vec<i64> {
+    intcode::load(path).expect("Something went wrong reading the file")
+}
+
+#[test]
+fn engines_agree_on_the_puzzle_inputs() {
+    let corpus = vec![
+        (load("../day02/input.txt"), vec![]),
+        (load("../day05/input.txt"), vec![1]),
+        (load("../day05/input.txt"), vec![5]),
+        (load("../day07/input.txt"), vec![0, 0]),
+        (load("../day07/input.txt"), vec![9, 0, 5, 7]),
+        (load("../day09/input.txt"), vec![1]),
+    ];
+
+    let mismatches = differential::run(&engines(), &corpus, BUDGET);
+
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+}
+
+#[test]
+fn engines_agree_on_generated_programs() {
+    let corpus: Vec<(Vec<i64>, Vec<i64>)> = (0..2000_u32).map(|seed| {
+        let bytes: Vec<u8> = (0..200_u32).map(|i| (seed.wrapping_mul(2654435761) ^ i.wrapping_mul(40503)).to_le_bytes()[i as usize % 4]).collect();
+        let generated = generator::generate(&bytes);
+        (generated.program, generated.inputs)
+    }).collect();
+
+    for budget in &[0, 1, 5, 100] {
+        let mismatches = differential::run(&engines(), &corpus, *budget);
+
+        assert!(mismatches.is_empty(), "{:#?}", mismatches);
+    }
+}
+
+// Gets additions wrong as soon as the result is larger than 100.
+struct Broken;
+
+impl Engine for Broken {
+    fn name(&self) -> &str {
+        "broken"
+    }
+
+    fn execute(&self, program: &[i64], inputs: &[i64], budget: usize) -> Execution {
+        let mut execution = Interpreter.execute(program, inputs, budget);
+
+        for output in execution.outputs.iter_mut().filter(|o| **o > 100) {
+            *output += 1;
+        }
+
+        execution
+    }
+}
+
+#[test]
+fn mismatches_are_minimized() {
+    let engines: Vec<Box<dyn Engine>> = vec![Box::new(Interpreter), Box::new(Broken)];
+    let program = load("../day05/input.txt");
+
+    let mismatches = differential::run(&engines, &[(program.clone(), vec![5])], BUDGET);
+
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].engine, "broken");
+    assert!(mismatches[0].program.len() < program.len() / 4, "{:?}", mismatches[0].program);
+    assert!(mismatches[0].inputs.is_empty());
+}
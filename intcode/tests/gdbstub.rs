@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+use intcode::gdbstub::Stub;
+use intcode::Machine;
+
+// A debugger that doesn't know much more than how to send packets.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn attach(stub: Stub) -> (Client, JoinHandle<Stub>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let mut stub = stub;
+            let (stream, _) = listener.accept().unwrap();
+            stub.serve(stream).unwrap();
+            stub
+        });
+
+        (Client { stream: TcpStream::connect(address).unwrap() }, server)
+    }
+
+    fn byte(&mut self) -> u8 {
+        let mut byte = [0];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", data, checksum).unwrap();
+        assert_eq!(self.byte(), b'+');
+    }
+
+    fn receive(&mut self) -> String {
+        assert_eq!(self.byte(), b'$');
+
+        let mut data = Vec::new();
+        loop {
+            match self.byte() {
+                b'#' => break,
+                byte => data.push(byte),
+            }
+        }
+
+        self.byte();
+        self.byte();
+        self.stream.write_all(b"+").unwrap();
+
+        String::from_utf8(data).unwrap()
+    }
+
+    fn request(&mut self, data: &str) -> String {
+        self.send(data);
+        self.receive()
+    }
+}
+
+// in [9], add [9], [10] -> [11], out [11], halt, with 5 at 10.
+const ADD_FIVE: &[i64] = &[3, 9, 1, 9, 10, 11, 4, 11, 99, 0, 5, 0];
+
+#[test]
+fn registers_and_memory_can_be_read_and_written() {
+    let (mut client, server) = Client::attach(Stub::new(Machine::new(ADD_FIVE)));
+
+    assert_eq!(client.request("?"), "S05");
+    assert!(client.request("qSupported:swbreak+").contains("qXfer:features:read+"));
+    assert!(client.request("qXfer:features:read:target.xml:0,1000").starts_with("l<?xml"));
+    assert_eq!(client.request("g"), "0000000000000000".repeat(2));
+    assert_eq!(client.request("m50,8"), "0500000000000000");
+    assert_eq!(client.request("M50,8:0700000000000000"), "OK");
+    assert_eq!(client.request("P1=f8ffffffffffffff"), "OK");
+    assert_eq!(client.request("p1"), "f8ffffffffffffff");
+    assert_eq!(client.request("vMustReplyEmpty"), "");
+
+    client.send("k");
+    let stub = server.join().unwrap();
+
+    assert_eq!(stub.machine().get(10), 7);
+    assert_eq!(stub.machine().relative_base(), -8);
+}
+
+#[test]
+fn breakpoints_and_steps_stop_the_machine() {
+    let (mut client, server) = Client::attach(Stub::new(Machine::new(ADD_FIVE)).with_input(vec![3]));
+
+    assert_eq!(client.request("Z0,30,1"), "OK");
+    assert_eq!(client.request("c"), "S05");
+    assert_eq!(client.request("p0"), "3000000000000000");
+    assert_eq!(client.request("z0,30,1"), "OK");
+    assert_eq!(client.request("m58,8"), "0800000000000000");
+
+    // Stepping over the output shows it on the console first.
+    assert_eq!(client.request("s"), "O380a");
+    assert_eq!(client.receive(), "S05");
+    assert_eq!(client.request("c"), "W00");
+
+    client.send("D");
+    assert_eq!(client.receive(), "OK");
+    assert!(server.join().unwrap().machine().is_halted());
+}
+
+#[test]
+fn running_machines_can_be_interrupted() {
+    let (mut client, server) = Client::attach(Stub::new(Machine::new(&[1105, 1, 0])));
+
+    assert_eq!(client.request("QStartNoAckMode"), "OK");
+    write!(client.stream, "$c#63").unwrap();
+    client.stream.write_all(&[0x03]).unwrap();
+
+    let mut reply = [0; 7];
+    client.stream.read_exact(&mut reply).unwrap();
+    assert_eq!(&reply, b"$S02#b5");
+
+    write!(client.stream, "$k#6b").unwrap();
+    server.join().unwrap();
+}
@@ -0,0 +1,80 @@
+use std::fs;
+
+use intcode::grid::{Frames, Grid, Palette, TooLarge};
+use intcode::{Machine, State};
+
+#[test]
+fn triples_are_drawn_with_the_palette() {
+    // 1,2,3,6,5,4 from the arcade cabinet: a paddle at 1,2 and a ball at 6,5, then a score of 12345
+    let program = [104, 1, 104, 2, 104, 3, 104, 6, 104, 5, 104, 4, 104, -1, 104, 0, 104, 12345, 99];
+    let palette = Palette::new('?').with(0, ' ').with(3, '-').with(4, 'o');
+    let mut grid = Grid::new(palette).with_score_at(-1, 0);
+
+    assert_eq!(Machine::new(&program).run(&mut None, &mut grid), Ok(State::Halted));
+    assert_eq!(grid.tile(6, 5), Some(4));
+    assert_eq!(grid.score(), Some(12345));
+    assert_eq!(grid.render().unwrap(), "-     \n      \n      \n     o\nScore: 12345\n");
+}
+
+#[test]
+fn default_palette_draws_like_day08() {
+    let mut grid = Grid::new(Palette::default());
+
+    for value in &[0, 0, 1, 1, 0, 0, 2, 0, 7] {
+        intcode::Output::write(&mut grid, *value);
+    }
+
+    assert_eq!(grid.render().unwrap(), "* *\n");
+}
+
+#[test]
+fn frames_can_go_to_files() {
+    let directory = std::env::temp_dir().join(format!("intcode-frames-{}", std::process::id()));
+    let mut grid = Grid::new(Palette::default()).with_frames(Frames::Files(directory.clone()));
+
+    intcode::Output::write(&mut grid, 0);
+    intcode::Output::write(&mut grid, 0);
+    intcode::Output::write(&mut grid, 1);
+    grid.frame().unwrap();
+    grid.frame().unwrap();
+
+    assert_eq!(fs::read_to_string(directory.join("frame-00002.txt")).unwrap(), "*\n");
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn frames_follow_the_input() {
+    // Draws a tile at the position it reads, twice, then halts.
+    let program = [3, 100, 4, 100, 104, 0, 104, 1, 3, 100, 4, 100, 104, 0, 104, 2, 99];
+    let directory = std::env::temp_dir().join(format!("intcode-run-frames-{}", std::process::id()));
+    let mut grid = Grid::new(Palette::default()).with_frames(Frames::Files(directory.clone()));
+
+    assert_eq!(grid.run(&mut Machine::new(&program), &mut vec![0, 2].into_iter()).unwrap(), State::Halted);
+
+    let frames: Vec<String> = (1..=3).map(|n| fs::read_to_string(directory.join(format!("frame-{:05}.txt", n))).unwrap()).collect();
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(frames, ["", "*\n", "* *\n"]);
+}
+
+#[test]
+fn far_apart_tiles_are_not_rendered() {
+    let mut grid = Grid::new(Palette::default());
+
+    for value in &[-i64::MAX / 2, 0, 1, i64::MAX / 2, 0, 1] {
+        intcode::Output::write(&mut grid, *value);
+    }
+
+    assert_eq!(grid.render(), Err(TooLarge { width: i64::MAX as u128, height: 1 }));
+}
+
+#[test]
+fn tiles_at_both_ends_of_the_range_are_not_rendered() {
+    let mut grid = Grid::new(Palette::default());
+
+    for value in &[i64::MIN, i64::MIN, 1, i64::MAX, i64::MAX, 1] {
+        intcode::Output::write(&mut grid, *value);
+    }
+
+    assert_eq!(grid.render(), Err(TooLarge { width: 1 << 64, height: 1 << 64 }));
+}
@@ -0,0 +1,41 @@
+use intcode::heap::{self, Region, Writers};
+use intcode::{BeyondLimit, Machine, State};
+
+#[test]
+fn changed_cells_are_grouped_into_regions() {
+    let before = [1, 2, 3, 4, 5];
+    let after = [1, 7, 8, 4, 5, 0, 6];
+
+    assert_eq!(heap::diff(&before, &after, None), vec![
+        Region { start: 1, old: vec![2, 3], new: vec![7, 8], writers: vec![None, None] },
+        Region { start: 6, old: vec![0], new: vec![6], writers: vec![None] },
+    ]);
+}
+
+#[test]
+fn day02_walkthrough_knows_its_writers() {
+    let mut machine = Machine::new(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+    let before = machine.memory().to_vec();
+    let mut writers = Writers::new();
+
+    assert_eq!(writers.run(&mut machine, &mut None, &mut Vec::new()), Ok(State::Halted));
+
+    let regions = heap::diff(&before, machine.memory(), Some(&writers));
+
+    assert_eq!(regions, vec![
+        Region { start: 0, old: vec![1], new: vec![3500], writers: vec![Some(4)] },
+        Region { start: 3, old: vec![3], new: vec![70], writers: vec![Some(0)] },
+    ]);
+    assert_eq!(regions[1].to_string(), "3..4:\n       3: 3 -> 70 (written at 0)\n");
+}
+
+#[test]
+fn cells_beyond_the_memory_limit_cannot_be_set() {
+    let mut machine = Machine::new(&[99]);
+
+    assert_eq!(machine.set(intcode::MEMORY_LIMIT - 1, 5), Ok(()));
+    assert_eq!(machine.get(intcode::MEMORY_LIMIT - 1), 5);
+    assert_eq!(machine.set(intcode::MEMORY_LIMIT, 5), Err(BeyondLimit { address: intcode::MEMORY_LIMIT }));
+    assert_eq!(machine.set(usize::MAX, 5), Err(BeyondLimit { address: usize::MAX }));
+    assert_eq!(machine.memory().len(), intcode::MEMORY_LIMIT);
+}
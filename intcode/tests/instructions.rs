@@ -0,0 +1,86 @@
+use intcode::assembler::{assemble, AssembleError};
+use intcode::disassembler::{self, Line};
+use intcode::instructions::{Effect, InstructionSet, RegisterError, Role};
+use intcode::{Error, Machine, State};
+
+fn extended() -> InstructionSet {
+    let mut instructions = InstructionSet::standard();
+
+    instructions.register(10, "div", &[Role::Read, Role::Read, Role::Write], |op| {
+        let divisor = op.parameter(2)?;
+        let quotient = op.parameter(1)?.checked_div(divisor).ok_or(Error::Overflow { address: op.address() })?;
+        op.write(3, quotient)?;
+        Ok(Effect::Next)
+    }).unwrap();
+    instructions.register(11, "mod", &[Role::Read, Role::Read, Role::Write], |op| {
+        let divisor = op.parameter(2)?;
+        let remainder = op.parameter(1)?.checked_rem(divisor).ok_or(Error::Overflow { address: op.address() })?;
+        op.write(3, remainder)?;
+        Ok(Effect::Next)
+    }).unwrap();
+
+    instructions
+}
+
+const DIVIDE: &str = "
+    # divides the input by 7, printing the quotient and the remainder
+            in [n]
+            div [n], 7, [quotient]
+            mod [n], 7, [remainder]
+            out [quotient]
+            out [remainder]
+            halt
+    n:      data 0
+    quotient: data 0
+    remainder: data 0
+";
+
+#[test]
+fn registered_opcodes_run_disassemble_and_assemble() {
+    let instructions = extended();
+    let program = assemble(&instructions, DIVIDE).unwrap();
+
+    assert_eq!(&program[..6], &[3, 15, 1010, 15, 7, 16]);
+
+    let mut machine = Machine::new(&program).with_instructions(instructions.clone());
+    let mut outputs = Vec::new();
+
+    assert_eq!(machine.run(&mut Some(30), &mut outputs), Ok(State::Halted));
+    assert_eq!(outputs, vec![4, 2]);
+
+    let lines = disassembler::disassemble_from(&instructions, &program, &Default::default());
+    assert_eq!(lines[1].to_string(), "     2: div [15], 7, [16]");
+    assert_eq!(Machine::new(&program).run(&mut Some(30), &mut Vec::new()), Err(Error::UnknownOpcode { address: 2, opcode: 10 }));
+    assert_eq!(disassembler::disassemble(&program)[1], Line::Data { address: 2, value: 1010 });
+}
+
+#[test]
+fn disassembly_assembles_back_into_the_same_program() {
+    for path in &["../day02/input.txt", "../day05/input.txt", "../day09/input.txt"] {
+        let program = intcode::load(path).unwrap();
+        let text: Vec<String> = disassembler::disassemble(&program).iter().map(|line| line.to_string()).collect();
+
+        assert_eq!(assemble(&InstructionSet::standard(), &text.join("\n")), Ok(program), "{}", path);
+    }
+}
+
+#[test]
+fn registrations_are_checked() {
+    let mut instructions = InstructionSet::standard();
+    let nothing = |_: &mut intcode::instructions::Operation| Ok(Effect::Next);
+
+    assert_eq!(instructions.register(2, "double", &[], nothing), Err(RegisterError::Taken { opcode: 2 }));
+    assert_eq!(instructions.register(100, "big", &[], nothing), Err(RegisterError::OutOfRange { opcode: 100 }));
+    assert_eq!(instructions.register(12, "add", &[], nothing), Err(RegisterError::DuplicateMnemonic { mnemonic: "add" }));
+}
+
+#[test]
+fn assembly_errors_say_where() {
+    let instructions = InstructionSet::standard();
+
+    assert_eq!(assemble(&instructions, "in 5"), Err(AssembleError::ImmediateWrite { line: 1 }));
+    assert_eq!(assemble(&instructions, "\nout 1, 2"), Err(AssembleError::ParameterCount { line: 2, expected: 1, found: 2 }));
+    assert_eq!(assemble(&instructions, "jz 0, end"), Err(AssembleError::UnknownLabel { line: 1, label: "end".to_string() }));
+    assert_eq!(assemble(&instructions, "div 1, 2, [0]"), Err(AssembleError::UnknownMnemonic { line: 1, mnemonic: "div".to_string() }));
+    assert_eq!(assemble(&instructions, "arb -3\nout [rb-1]\nadd [rb], 1, [rb+2]"), Ok(vec![109, -3, 204, -1, 21201, 0, 1, 2]));
+}
@@ -0,0 +1,73 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use intcode::loader::{decode, encode_binary, parse};
+use intcode::LoadError;
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn trailing_newline_is_not_a_value() {
+    assert_eq!(parse("1,0,0,0,99\n").unwrap(), vec![1, 0, 0, 0, 99]);
+    assert_eq!(parse("  1, 0 ,0,0,99 \r\n").unwrap(), vec![1, 0, 0, 0, 99]);
+}
+
+#[test]
+fn values_can_be_spread_over_lines() {
+    let text = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\n27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5\n";
+
+    assert_eq!(parse(text).unwrap().len(), 29);
+    assert_eq!(parse("1,9,10,3\n2,3,11,0\n99\n30,40,50").unwrap(), vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+}
+
+#[test]
+fn whitespace_separates_values_too() {
+    assert_eq!(parse("1 2 3").unwrap(), vec![1, 2, 3]);
+    assert_eq!(parse("1\t2 ,3,\n").unwrap(), vec![1, 2, 3]);
+    assert!(parse("1 ,, 2").is_err());
+}
+
+#[test]
+fn malformed_values_are_reported_where_they_are() {
+    match parse("1,2,3\n4,x5,6") {
+        Err(LoadError::Malformed { offset, line, column, token }) => {
+            assert_eq!((offset, line, column, token.as_str()), (8, 2, 3, "x5"));
+        },
+        other => panic!("{:?}", other),
+    }
+
+    match parse("1,,2") {
+        Err(LoadError::Malformed { offset, token, .. }) => assert_eq!((offset, token.as_str()), (2, "")),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn binary_format_round_trips() {
+    let program = vec![0, 1, -1, 63, -64, 64, 1125899906842624, i64::MAX, i64::MIN, 99];
+
+    assert_eq!(decode(&encode_binary(&program)).unwrap(), program);
+}
+
+#[test]
+fn truncated_binary_is_an_error() {
+    let mut bytes = encode_binary(&[5, 1125899906842624]);
+    bytes.pop();
+
+    match decode(&bytes) {
+        Err(LoadError::Truncated { offset }) => assert_eq!(offset, 5),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn gzip_is_detected() {
+    assert_eq!(decode(&gzip(b"104,1125899906842624,99\n")).unwrap(), vec![104, 1125899906842624, 99]);
+    assert_eq!(decode(&gzip(&encode_binary(&[3, 0, 4, 0, 99]))).unwrap(), vec![3, 0, 4, 0, 99]);
+}
@@ -0,0 +1,114 @@
+use std::num::NonZeroUsize;
+
+use intcode::{Error, Machine, Network, Outcome, Packet, Route, Transmission, UnknownAddress};
+
+// The feedback loop example from day 7, with the phase settings 9,8,7,6,5.
+const FEEDBACK: &[i64] = &[3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005, 28, 6, 99, 0, 0, 5];
+
+#[test]
+fn amplifiers_run_in_a_loop() {
+    let mut network = Network::new();
+
+    for (i, phase) in [9, 8, 7, 6, 5].iter().enumerate() {
+        network.add(Machine::new(FEEDBACK), Route::Forward(vec![(i + 1) % 5]));
+        network.send(i, *phase).unwrap();
+    }
+    network.send(0, 0).unwrap();
+
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(network.last_output(4), Some(139629729));
+}
+
+#[test]
+fn sending_to_nobody_is_an_error() {
+    let mut network = Network::new();
+    network.add(Machine::new(&[99]), Route::Outbox);
+
+    assert_eq!(network.send(1, 5), Err(UnknownAddress { address: 1 }));
+}
+
+#[test]
+fn outputs_before_a_failure_are_delivered() {
+    let mut network = Network::new();
+    network.add(Machine::new(&[104, 5, 104, 6, 98]), Route::Outbox);
+
+    assert_eq!(network.run(), Err(Error::UnknownOpcode { address: 4, opcode: 98 }));
+    assert_eq!(network.outbox(), &[
+        Packet { source: 0, address: None, values: vec![5] },
+        Packet { source: 0, address: None, values: vec![6] },
+    ]);
+}
+
+#[test]
+fn packets_cut_short_by_a_halt_are_reported() {
+    let mut network = Network::new();
+    // Sends 7,9 to machine 1 and halts after the first value of another packet for it.
+    network.add(Machine::new(&[104, 1, 104, 7, 104, 9, 104, 1, 104, 8, 99]), Route::Packets(3));
+    network.add(Machine::new(&[99]), Route::Outbox);
+
+    assert_eq!(network.run(), Ok(Outcome::Unfinished(vec![Packet { source: 0, address: Some(1), values: vec![8] }])));
+}
+
+#[test]
+fn packets_go_to_the_address_they_start_with() {
+    let mut network = Network::new();
+    network.add(Machine::new(&[104, 1, 104, 10, 104, 20, 99]), Route::Packets(3));
+    // Outputs the sum of the two values it reads.
+    network.add(Machine::new(&[3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0]), Route::Outbox);
+    network.record_transmissions();
+
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(network.outbox(), &[Packet { source: 1, address: None, values: vec![30] }]);
+    assert_eq!(network.transmissions(), &[
+        Transmission { source: 0, destination: Some(1), value: 10 },
+        Transmission { source: 0, destination: Some(1), value: 20 },
+        Transmission { source: 1, destination: None, value: 30 },
+    ]);
+}
+
+#[test]
+fn packets_to_unknown_addresses_end_up_in_the_outbox() {
+    let mut network = Network::new();
+    network.add(Machine::new(&[104, 5, 104, 42, 104, -1, 104, 7, 99]), Route::Packets(2));
+
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(network.outbox(), &[
+        Packet { source: 0, address: Some(5), values: vec![42] },
+        Packet { source: 0, address: Some(-1), values: vec![7] },
+    ]);
+}
+
+#[test]
+fn slices_take_turns() {
+    let program = |value| Machine::new(&[104, value, 104, value, 104, value, 99]);
+    let outbox = |network: &Network| network.outbox().iter().map(|p| p.values[0]).collect::<Vec<_>>();
+
+    let mut network = Network::new();
+    network.add(program(1), Route::Outbox);
+    network.add(program(2), Route::Outbox);
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(outbox(&network), vec![1, 1, 1, 2, 2, 2]);
+
+    let mut network = Network::new();
+    network.add(program(1), Route::Outbox);
+    network.add(program(2), Route::Outbox);
+    network.set_slice(NonZeroUsize::new(1).unwrap());
+    assert_eq!(network.run(), Ok(Outcome::Halted));
+    assert_eq!(outbox(&network), vec![1, 2, 1, 2, 1, 2]);
+}
+
+#[test]
+fn machines_waiting_on_each_other_deadlock() {
+    let mut network = Network::new();
+    // Passes on the sum of two values, but each of them only ever gets one.
+    let adder = [3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+
+    network.add(Machine::new(&adder), Route::Forward(vec![1]));
+    network.add(Machine::new(&adder), Route::Forward(vec![0]));
+    network.add(Machine::new(&[99]), Route::Outbox);
+    network.send(0, 5).unwrap();
+    network.send(1, 6).unwrap();
+
+    assert_eq!(network.run(), Ok(Outcome::Deadlock(vec![0, 1])));
+    assert_eq!(network.last_output(0), None);
+}
@@ -0,0 +1,36 @@
+use intcode::{Error, Machine, Profile, State};
+
+fn run(program: &[i64], input: i64, profile: Profile) -> Result<State, Error> {
+    let mut machine = Machine::new(program).with_profile(profile);
+    machine.run(&mut Some(input), &mut Vec::new())
+}
+
+#[test]
+fn every_day_runs_on_its_own_profile() {
+    let mut day02 = intcode::load("../day02/input.txt").unwrap();
+    day02[1] = 12;
+    day02[2] = 2;
+
+    assert_eq!(run(&day02, 0, Profile::Day02), Ok(State::Halted));
+    assert_eq!(run(&intcode::load("../day05/input.txt").unwrap(), 5, Profile::Day05), Ok(State::Halted));
+    assert_eq!(run(&intcode::load("../day09/input.txt").unwrap(), 1, Profile::Full), Ok(State::Halted));
+}
+
+#[test]
+fn newer_features_are_rejected() {
+    assert_eq!(run(&[3, 0, 99], 1, Profile::Day02), Err(Error::UnsupportedOpcode { address: 0, opcode: 3 }));
+    assert_eq!(run(&[1002, 4, 3, 4, 33], 1, Profile::Day02), Err(Error::UnsupportedMode { address: 0, mode: 1 }));
+    assert_eq!(run(&[109, 1, 99], 1, Profile::Day05), Err(Error::UnsupportedOpcode { address: 0, opcode: 9 }));
+    assert_eq!(run(&[204, 0, 99], 1, Profile::Day05), Err(Error::UnsupportedMode { address: 0, mode: 2 }));
+    assert!(run(&intcode::load("../day09/input.txt").unwrap(), 1, Profile::Day05).is_err());
+}
+
+#[test]
+fn custom_profiles_pick_their_features() {
+    let profile = Profile::Custom { opcodes: vec![1, 4, 99], modes: vec![0, 1] };
+
+    assert_eq!(run(&[1101, 1, 2, 0, 104, 0, 99], 0, profile.clone()), Ok(State::Halted));
+    assert_eq!(run(&[2, 0, 0, 0, 99], 0, profile.clone()), Err(Error::UnsupportedOpcode { address: 0, opcode: 2 }));
+    assert_eq!(run(&[204, 0, 99], 0, profile.clone()), Err(Error::UnsupportedMode { address: 0, mode: 2 }));
+    assert_eq!(run(&[42, 0, 99], 0, profile), Err(Error::UnknownOpcode { address: 0, opcode: 42 }));
+}
@@ -0,0 +1,139 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::{json, Value};
+
+use intcode::server::{Server, MAX_REQUEST};
+
+fn request(server: &Server, request: Value) -> Value {
+    serde_json::from_str(&server.handle(&request.to_string())).unwrap()
+}
+
+#[test]
+fn machines_run_until_they_need_input() {
+    let server = Server::new();
+
+    let program = request(&server, json!({ "command": "load", "program": "3,0,4,0,3,0,4,0,99" }))["program"].clone();
+    let machine = request(&server, json!({ "command": "create", "program": program }))["machine"].clone();
+
+    assert_eq!(request(&server, json!({ "command": "run", "machine": machine, "input": [7], "id": "a" })),
+        json!({ "ok": true, "state": "waiting", "outputs": [7], "id": "a" }));
+    assert_eq!(request(&server, json!({ "command": "input", "machine": machine, "values": [8, 9] })), json!({ "ok": true }));
+    assert_eq!(request(&server, json!({ "command": "run", "machine": machine })),
+        json!({ "ok": true, "state": "halted", "outputs": [8] }));
+
+    let snapshot = request(&server, json!({ "command": "snapshot", "machine": machine }));
+    assert_eq!(snapshot["halted"], json!(true));
+    assert_eq!(snapshot["input"], json!([9]));
+    assert_eq!(snapshot["memory"][0], json!(8));
+
+    assert_eq!(request(&server, json!({ "command": "destroy", "machine": machine })), json!({ "ok": true }));
+    assert_eq!(request(&server, json!({ "command": "run", "machine": machine }))["error"], json!("there is no machine 2"));
+}
+
+#[test]
+fn bad_requests_are_answered_with_errors() {
+    let server = Server::new();
+
+    assert_eq!(request(&server, json!({ "command": "fly" }))["error"], json!("unknown command fly"));
+    assert_eq!(request(&server, json!({ "command": "create", "program": 5 }))["ok"], json!(false));
+    assert_eq!(serde_json::from_str::<Value>(&server.handle("not json")).unwrap()["ok"], json!(false));
+
+    let program = request(&server, json!({ "command": "load", "program": [1, 0, 0, 0, 99] }))["program"].clone();
+    let machine = request(&server, json!({ "command": "create", "program": program }))["machine"].clone();
+
+    assert_eq!(request(&server, json!({ "command": "run", "machine": machine, "steps": 1 }))["state"], json!("running"));
+}
+
+#[test]
+fn runs_stop_on_their_own() {
+    let server = Server::new();
+
+    let broken = request(&server, json!({ "command": "load", "program": "104,5,98" }))["program"].clone();
+    let machine = request(&server, json!({ "command": "create", "program": broken }))["machine"].clone();
+    let response = request(&server, json!({ "command": "run", "machine": machine }));
+
+    assert_eq!(response["ok"], json!(false));
+    assert_eq!(response["outputs"], json!([5]));
+
+    let endless = request(&server, json!({ "command": "load", "program": "1105,1,0" }))["program"].clone();
+    let machine = request(&server, json!({ "command": "create", "program": endless }))["machine"].clone();
+
+    assert_eq!(request(&server, json!({ "command": "run", "machine": machine })),
+        json!({ "ok": true, "state": "running", "outputs": [] }));
+}
+
+// Sends a request over a connection and reads the answer.
+fn exchange<S: std::io::Read + Write>(writer: &mut S, reader: &mut BufReader<S>, request: Value) -> Value {
+    writeln!(writer, "{}", request).unwrap();
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    serde_json::from_str(&line).unwrap()
+}
+
+#[test]
+fn clients_share_machines_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = Arc::new(Server::new());
+    thread::spawn(move || server.listen_tcp(listener));
+
+    let connect = || {
+        let stream = TcpStream::connect(address).unwrap();
+        (stream.try_clone().unwrap(), BufReader::new(stream))
+    };
+
+    let (mut first, mut first_reader) = connect();
+    let program = exchange(&mut first, &mut first_reader, json!({ "command": "load", "program": "3,0,4,0,99" }))["program"].clone();
+
+    let clients: Vec<_> = (0..4).map(|i| {
+        let (mut stream, mut reader) = connect();
+        let program = program.clone();
+
+        thread::spawn(move || {
+            let machine = exchange(&mut stream, &mut reader, json!({ "command": "create", "program": program }))["machine"].clone();
+            exchange(&mut stream, &mut reader, json!({ "command": "run", "machine": machine, "input": [i] }))
+        })
+    }).collect();
+
+    for (i, client) in clients.into_iter().enumerate() {
+        assert_eq!(client.join().unwrap(), json!({ "ok": true, "state": "halted", "outputs": [i] }));
+    }
+}
+
+#[test]
+fn unix_sockets_speak_the_same_protocol() {
+    let path = std::env::temp_dir().join(format!("intcode-server-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let server = Arc::new(Server::new());
+    thread::spawn(move || server.listen_unix(listener));
+
+    let mut stream = UnixStream::connect(&path).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    assert_eq!(exchange(&mut stream, &mut reader, json!({ "command": "load", "program": [99] })), json!({ "ok": true, "program": 1 }));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn overlong_requests_end_the_connection() {
+    let server = Server::new();
+    let mut input = format!("{}\n", json!({ "command": "load", "program": [99] })).into_bytes();
+    input.extend(vec![b' '; MAX_REQUEST + 1]);
+    input.extend(format!("{}\n", json!({ "command": "load", "program": [99] })).into_bytes());
+
+    let mut output = Vec::new();
+    server.serve(&input[..], &mut output).unwrap();
+    let answers: Vec<Value> = output.split(|b| *b == b'\n').filter(|l| !l.is_empty()).map(|l| serde_json::from_slice(l).unwrap()).collect();
+
+    assert_eq!(answers, vec![
+        json!({ "ok": true, "program": 1 }),
+        json!({ "ok": false, "error": format!("requests can be at most {} bytes long", MAX_REQUEST) }),
+    ]);
+}
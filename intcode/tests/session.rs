@@ -0,0 +1,71 @@
+use intcode::session::{self, Recorder, ReplayError, Session};
+use intcode::{Machine, State};
+
+// Adds up inputs until it reads a 0, printing the sum so far after each one.
+const SUM: &[i64] = &[3, 100, 1006, 100, 14, 1, 100, 101, 101, 4, 101, 1105, 1, 0, 99];
+
+fn record(inputs: &[i64]) -> Session {
+    let mut machine = Machine::new(SUM);
+    let mut recorder = Recorder::new();
+    let mut outputs = Vec::new();
+
+    for input in inputs {
+        recorder.run(&mut machine, &mut None, &mut outputs).unwrap();
+        recorder.run(&mut machine, &mut Some(*input), &mut outputs).unwrap();
+    }
+
+    recorder.into_session()
+}
+
+#[test]
+fn sessions_are_recorded_and_saved() {
+    let session = record(&[3, 4, 0]);
+
+    assert_eq!(session.inputs().collect::<Vec<_>>(), vec![3, 4, 0]);
+    assert_eq!(session.to_string(), "> 3\n< 3\n> 4\n< 7\n> 0\n");
+    assert_eq!(Session::parse(&session.to_string()), Ok(session));
+}
+
+#[test]
+fn replays_give_the_same_outputs() {
+    let replayed = session::replay(SUM, &record(&[3, 4, 0]), None).unwrap();
+
+    assert_eq!(replayed.state, State::Halted);
+    assert_eq!(replayed.inputs, 3);
+}
+
+#[test]
+fn replays_stop_before_an_input() {
+    let replayed = session::replay(SUM, &record(&[3, 4, 0]), Some(2)).unwrap();
+
+    assert_eq!(replayed.state, State::WaitingForInput);
+    assert_eq!(replayed.inputs, 2);
+    assert_eq!(replayed.outputs, vec![3, 7]);
+    assert_eq!(replayed.machine.get(101), 7);
+}
+
+#[test]
+fn replays_notice_different_outputs() {
+    let session = Session::parse("> 3\n< 4\n> 0\n").unwrap();
+
+    assert_eq!(
+        session::replay(SUM, &session, None).unwrap_err(),
+        ReplayError::Diverged { input: 1, expected: vec![4], actual: vec![3] },
+    );
+    assert_eq!(session::replay(SUM, &Session::parse("> 0\n> 1\n").unwrap(), None).unwrap_err(), ReplayError::Halted { input: 1 });
+}
+
+#[test]
+fn recordings_continue_after_a_replay() {
+    let replayed = session::replay(SUM, &record(&[3, 4, 0]), Some(1)).unwrap();
+    let mut machine = replayed.machine;
+    let mut recorder = Recorder::resume(record(&[3, 4, 0]).prefix(replayed.inputs));
+
+    recorder.run(&mut machine, &mut Some(5), &mut Vec::new()).unwrap();
+    recorder.run(&mut machine, &mut Some(0), &mut Vec::new()).unwrap();
+
+    let session = recorder.into_session();
+
+    assert_eq!(session, record(&[3, 5, 0]));
+    assert_eq!(session::replay(SUM, &session, None).unwrap().state, State::Halted);
+}
@@ -0,0 +1,56 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use intcode::taint::Taint;
+use intcode::{Machine, State};
+
+fn sources(inputs: &[usize]) -> BTreeSet<usize> {
+    inputs.iter().cloned().collect()
+}
+
+#[test]
+fn arithmetic_passes_inputs_on() {
+    // in [15], in [16], out [15], add [15], [16] -> [17], out [17], out 5, halt
+    let program = [3, 15, 3, 16, 4, 15, 1, 15, 16, 17, 4, 17, 104, 5, 99];
+    let mut machine = Machine::new(&program);
+    let mut taint = Taint::new();
+    let mut outputs = Vec::new();
+
+    assert_eq!(taint.run(&mut machine, &mut VecDeque::from(vec![2, 3]), &mut outputs), Ok(State::Halted));
+
+    let provenance: Vec<_> = taint.outputs().iter().map(|p| (p.value, p.sources.clone())).collect();
+
+    assert_eq!(outputs, vec![2, 5, 5]);
+    assert_eq!(provenance, vec![(2, sources(&[0])), (5, sources(&[0, 1])), (5, sources(&[]))]);
+    assert_eq!(taint.sources(17), sources(&[0, 1]));
+    assert_eq!(taint.inputs(), 2);
+}
+
+#[test]
+fn branches_make_everything_after_them_depend_on_their_condition() {
+    // The day 5 example that checks whether the input is 8, with jumps. Its output is a constant picked by a
+    // jump on the input.
+    let program = [3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+    let mut machine = Machine::new(&program);
+    let mut taint = Taint::new();
+
+    assert_eq!(taint.run(&mut machine, &mut Some(0), &mut Vec::new()), Ok(State::Halted));
+    assert_eq!(taint.outputs()[0].value, 0);
+    assert_eq!(taint.outputs()[0].sources, sources(&[0]));
+    assert_eq!(taint.control(), &sources(&[0]));
+}
+
+#[test]
+fn day07_amplifier_outputs_depend_on_phase_and_signal() {
+    let program = intcode::load("../day07/input.txt").expect("Something went wrong reading the input");
+    let mut machine = Machine::new(&program);
+    let mut taint = Taint::new();
+    let mut inputs = VecDeque::from(vec![5, 0]);
+
+    assert_eq!(taint.run(&mut machine, &mut inputs, &mut Vec::new()), Ok(State::WaitingForInput));
+    inputs.push_back(7);
+    assert_eq!(taint.run(&mut machine, &mut inputs, &mut Vec::new()), Ok(State::WaitingForInput));
+
+    let provenance: Vec<_> = taint.outputs().iter().map(|p| p.sources.clone()).collect();
+
+    assert_eq!(provenance, vec![sources(&[0, 1]), sources(&[0, 2])]);
+}